@@ -0,0 +1,248 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use logmap::FilterId;
+
+/// Errors returned when loading or saving `LogFilters`
+///
+/// `path` is `None` when filters were not read from / written to a file.
+/// Line numbers are 1-based and count from the start of the filter file.
+/// Variants depend on enabled features, so matches need a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LogmapError {
+    /// Underlying I/O operation failed
+    Io {
+        path: Option<PathBuf>,
+        source: io::Error,
+    },
+    /// Header is missing or has unexpected content at `line`
+    CorruptHeader {
+        path: Option<PathBuf>,
+        line: usize,
+        found: String,
+    },
+    /// File was written in a format version this build can't read,
+    /// `supported` is the newest version of the same format this build reads
+    UnsupportedVersion {
+        path: Option<PathBuf>,
+        version: u64,
+        supported: u32,
+    },
+    /// Binary file has invalid content at byte `offset`
+    CorruptBinary {
+        path: Option<PathBuf>,
+        offset: usize,
+        reason: &'static str,
+    },
+    /// File is compressed with method this build was compiled without
+    UnsupportedCompression {
+        path: Option<PathBuf>,
+        compression: &'static str,
+    },
+    /// File was learned with tokenizer setting this build can't reproduce,
+    /// `expected` is `None` when the setting is unknown
+    UnsupportedTokenizer {
+        path: Option<PathBuf>,
+        setting: String,
+        value: String,
+        expected: Option<String>,
+    },
+    /// Parameter stored at `line` could not be parsed
+    BadParameter {
+        path: Option<PathBuf>,
+        line: usize,
+        value: String,
+    },
+    /// Filter stored at `line_no` is malformed
+    BadFilterLine {
+        path: Option<PathBuf>,
+        line_no: usize,
+    },
+    /// Rule stored at `line` is malformed
+    BadRuleLine {
+        path: Option<PathBuf>,
+        line: usize,
+        reason: String,
+    },
+    /// Configuration value was rejected by `LogFiltersConfig::validate`
+    InvalidConfig {
+        parameter: &'static str,
+        value: String,
+        reason: &'static str,
+    },
+    /// No filter has given identifier
+    UnknownFilter { id: FilterId },
+    /// Filter has no column under given index
+    UnknownColumn { id: FilterId, column: usize },
+    /// Word can't be stored as an alternative
+    InvalidWord { word: String },
+    /// Filter given as whole can't be used
+    InvalidFilter { id: FilterId, reason: &'static str },
+    /// Rule can't be built from given pattern
+    InvalidRule { rule: String, reason: String },
+    /// JSON document is malformed or does not describe filters
+    #[cfg(feature = "json")]
+    Json {
+        path: Option<PathBuf>,
+        source: serde_json::Error,
+    },
+}
+
+impl LogmapError {
+    pub fn io(source: io::Error) -> Self {
+        LogmapError::Io { path: None, source }
+    }
+
+    /// Attach `path` to the error unless it already refers to a file
+    pub fn with_path(mut self, new_path: &Path) -> Self {
+        {
+            let path = match self {
+                LogmapError::Io { ref mut path, .. }
+                | LogmapError::CorruptHeader { ref mut path, .. }
+                | LogmapError::UnsupportedVersion { ref mut path, .. }
+                | LogmapError::CorruptBinary { ref mut path, .. }
+                | LogmapError::UnsupportedCompression { ref mut path, .. }
+                | LogmapError::UnsupportedTokenizer { ref mut path, .. }
+                | LogmapError::BadParameter { ref mut path, .. }
+                | LogmapError::BadFilterLine { ref mut path, .. }
+                | LogmapError::BadRuleLine { ref mut path, .. } => path,
+                #[cfg(feature = "json")]
+                LogmapError::Json { ref mut path, .. } => path,
+                _ => return self,
+            };
+            if path.is_none() {
+                *path = Some(new_path.to_path_buf());
+            }
+        }
+        self
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match *self {
+            LogmapError::Io { ref path, .. }
+            | LogmapError::CorruptHeader { ref path, .. }
+            | LogmapError::UnsupportedVersion { ref path, .. }
+            | LogmapError::CorruptBinary { ref path, .. }
+            | LogmapError::UnsupportedCompression { ref path, .. }
+            | LogmapError::UnsupportedTokenizer { ref path, .. }
+            | LogmapError::BadParameter { ref path, .. }
+            | LogmapError::BadFilterLine { ref path, .. }
+            | LogmapError::BadRuleLine { ref path, .. } => path.as_ref().map(|p| p.as_path()),
+            #[cfg(feature = "json")]
+            LogmapError::Json { ref path, .. } => path.as_ref().map(|p| p.as_path()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LogmapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location = match self.path() {
+            Some(path) => format!("{}", path.display()),
+            None => "<input>".to_string(),
+        };
+        match *self {
+            LogmapError::Io { ref source, .. } => write!(f, "{}: {}", location, source),
+            LogmapError::CorruptHeader {
+                line, ref found, ..
+            } => write!(
+                f,
+                "{}:{}: file is corrupted, unexpected header content: {:?}",
+                location, line, found
+            ),
+            LogmapError::UnsupportedVersion {
+                version, supported, ..
+            } => write!(
+                f,
+                "{}: file format version {} is not supported, newest supported version is {}",
+                location, version, supported
+            ),
+            LogmapError::CorruptBinary { offset, reason, .. } => write!(
+                f,
+                "{}: file is corrupted at byte {}: {}",
+                location, offset, reason
+            ),
+            LogmapError::UnsupportedCompression { compression, .. } => write!(
+                f,
+                "{}: {} compression is not supported, enable `{}` feature",
+                location, compression, compression
+            ),
+            LogmapError::UnsupportedTokenizer {
+                ref setting,
+                ref value,
+                expected: Some(ref expected),
+                ..
+            } => write!(
+                f,
+                "{}: filters were learned with tokenizer setting `{}` = {:?}, this build uses {:?}",
+                location, setting, value, expected
+            ),
+            LogmapError::UnsupportedTokenizer {
+                ref setting,
+                ref value,
+                expected: None,
+                ..
+            } => write!(
+                f,
+                "{}: filters were learned with tokenizer setting `{}` = {:?} unknown to this build",
+                location, setting, value
+            ),
+            LogmapError::BadParameter {
+                line, ref value, ..
+            } => write!(
+                f,
+                "{}:{}: couldn't parse parameter: {:?}",
+                location, line, value
+            ),
+            LogmapError::BadFilterLine { line_no, .. } => {
+                write!(f, "{}:{}: malformed filter line", location, line_no)
+            }
+            LogmapError::BadRuleLine {
+                line, ref reason, ..
+            } => write!(f, "{}:{}: invalid rule: {}", location, line, reason),
+            LogmapError::InvalidConfig {
+                parameter,
+                ref value,
+                reason,
+            } => write!(f, "invalid `{}` value {:?}: {}", parameter, value, reason),
+            LogmapError::UnknownFilter { id } => write!(f, "no filter with id {}", id),
+            LogmapError::UnknownColumn { id, column } => {
+                write!(f, "filter {} has no column {}", id, column)
+            }
+            LogmapError::InvalidWord { ref word } => {
+                write!(f, "{:?} can't be used as word alternative", word)
+            }
+            LogmapError::InvalidFilter { id, reason } => {
+                write!(f, "filter {} is invalid: {}", id, reason)
+            }
+            LogmapError::InvalidRule {
+                ref rule,
+                ref reason,
+            } => write!(f, "rule {:?} is invalid: {}", rule, reason),
+            #[cfg(feature = "json")]
+            LogmapError::Json { ref source, .. } => {
+                write!(f, "{}: invalid JSON document: {}", location, source)
+            }
+        }
+    }
+}
+
+impl Error for LogmapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            LogmapError::Io { ref source, .. } => Some(source),
+            #[cfg(feature = "json")]
+            LogmapError::Json { ref source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LogmapError {
+    fn from(source: io::Error) -> Self {
+        LogmapError::io(source)
+    }
+}
@@ -1 +1,14 @@
+#[cfg(feature = "gzip")]
+extern crate flate2;
+extern crate regex;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(any(feature = "json", all(test, feature = "serde")))]
+extern crate serde_json;
+#[cfg(feature = "zstd")]
+extern crate zstd;
+
+pub mod error;
 pub mod logmap;
+pub mod mask;
+pub mod tokenizer;
@@ -1,194 +1,2041 @@
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
 use std::collections::HashMap;
-use std::error::Error;
-use std::fs::File;
+use std::collections::HashSet;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io;
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::slice;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use error::LogmapError;
+use mask::{Mask, Masker};
+use regex::{Regex, RegexSet};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tokenizer::{self, PunctuationTokenizer, Tokenizer, DEFAULT_WORD_DELIMITERS};
+
+/// Version of filter file format written by `LogFilters::save_to`
+pub const FORMAT_VERSION: u32 = 2;
+/// Start of the first line of filter files since format version 2, followed by version
+const FORMAT_HEADER: &str = "logmap-filters ";
+/// Characters of words treated as numeric by `ignore_numeric_words`, next to digits
+const NUMERIC_SYMBOLS: &[char] = &['*', '#'];
+/// Prefix of filter file parameters holding settings of `LogFilters::line_to_words`,
+/// see `LogFilters::tokenizer_settings`
+const TOKENIZER_PARAMETER_PREFIX: &str = "tokenizer.";
+/// Start of comment lines in filter files, see `LogFilters::set_note`
+const COMMENT_PREFIX: &str = "#";
+/// Template word standing for any single word, see `Rule::template`
+pub const TEMPLATE_ANY_WORD: &str = "<*>";
+/// Template word standing for any number of words, see `Rule::template`
+pub const TEMPLATE_ANY_WORDS: &str = "<**>";
+/// Number of lines holding parameters at the top of a version 1 filter file
+const V1_HEADER_LINES: usize = 4;
+/// Version of JSON documents written by `LogFilters::save_json_to`
+#[cfg(feature = "json")]
+pub const JSON_FORMAT_VERSION: u32 = 1;
+
+/// Start of files written by `LogFilters::save_binary_to`
+const BINARY_MAGIC: &[u8] = b"LOGMAP\0B";
+/// Version of binary format written by `LogFilters::save_binary_to`
+pub const BINARY_FORMAT_VERSION: u32 = 1;
+
+/// Format of files written by `LogFilters::save_as` and read by `LogFilters::load_as`
+///
+/// `Json` exists only with `json` feature, so matches need a wildcard arm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum FileFormat {
+    /// Text format written by `LogFilters::save_to`
+    Text,
+    /// JSON document written by `LogFilters::save_json_to`
+    #[cfg(feature = "json")]
+    Json,
+    /// Compact binary format written by `LogFilters::save_binary_to`
+    Binary,
+}
+
+impl FromStr for FileFormat {
+    type Err = LogmapError;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(FileFormat::Text),
+            "binary" => Ok(FileFormat::Binary),
+            #[cfg(feature = "json")]
+            "json" => Ok(FileFormat::Json),
+            _ => Err(LogmapError::InvalidConfig {
+                parameter: "format",
+                value: format.to_string(),
+                reason: "unknown or unsupported file format",
+            }),
+        }
+    }
+}
+
+impl FileFormat {
+    /// Format of file starting with `bytes`, text unless they match binary or JSON start
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(BINARY_MAGIC) {
+            return FileFormat::Binary;
+        }
+        #[cfg(feature = "json")]
+        {
+            if bytes.starts_with(b"{") {
+                return FileFormat::Json;
+            }
+        }
+        FileFormat::Text
+    }
+}
+
+/// Compression applied on top of `FileFormat` by `LogFilters::save_as` and `LogFilters::load_as`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    /// File content is stored as it is
+    None,
+    /// gzip, needs `gzip` feature
+    Gzip,
+    /// Zstandard, needs `zstd` feature
+    Zstd,
+}
+
+/// First bytes of gzip stream
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+/// First bytes of Zstandard frame
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+impl Compression {
+    /// Compression used when saving under `path`, chosen by `.gz` or `.zst` extension
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// Compression of file starting with `bytes`
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if bytes.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+}
+
+/// Default for `LogFiltersConfig::max_allowed_new_alternatives`
+pub const DEFAULT_MAX_ALLOWED_NEW_ALTERNATIVES: usize = 0;
+/// Default for `LogFiltersConfig::denote_optional`
+pub const DEFAULT_DENOTE_OPTIONAL: &str = ".";
+/// Default for `LogFiltersConfig::ignore_numeric_words`
+pub const DEFAULT_IGNORE_NUMERIC_WORDS: bool = true;
+/// Default for `LogFiltersConfig::ignore_first_columns`
+pub const DEFAULT_IGNORE_FIRST_COLUMNS: usize = 2;
+
+/// Builder of validated `LogFilters` parameters
+///
+/// Values that were never set fall back to defaults when building new filters
+/// and are left untouched when passed to `LogFilters::apply_overrides`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct LogFiltersConfig {
+    max_allowed_new_alternatives: Option<usize>,
+    denote_optional: Option<String>,
+    ignore_numeric_words: Option<bool>,
+    ignore_first_columns: Option<usize>,
+    tokenizer: Option<SharedTokenizer>,
+    masks: Option<Masker>,
+}
+
+impl LogFiltersConfig {
+    pub fn new() -> Self {
+        LogFiltersConfig::default()
+    }
+
+    /// Maximum allowed new alternatives when analysing any new line
+    pub fn with_max_allowed_new_alternatives(mut self, value: usize) -> Self {
+        self.max_allowed_new_alternatives = Some(value);
+        self
+    }
+
+    /// Marker stored within alternatives of optional columns
+    pub fn with_denote_optional(mut self, value: &str) -> Self {
+        self.denote_optional = Some(value.to_string());
+        self
+    }
+
+    /// Should words that contain only numbers be ignored
+    pub fn with_ignore_numeric_words(mut self, value: bool) -> Self {
+        self.ignore_numeric_words = Some(value);
+        self
+    }
+
+    /// Drop first columns before analysing
+    pub fn with_ignore_first_columns(mut self, value: usize) -> Self {
+        self.ignore_first_columns = Some(value);
+        self
+    }
+
+    /// Splits log lines into words, see `LogFilters::line_split`
+    pub fn with_tokenizer<T: Tokenizer + 'static>(mut self, tokenizer: T) -> Self {
+        self.tokenizer = Some(SharedTokenizer(Arc::new(tokenizer)));
+        self
+    }
+
+    /// Same as `with_tokenizer(PunctuationTokenizer::new(value))`
+    pub fn with_word_delimiters(self, value: &str) -> Self {
+        self.with_tokenizer(PunctuationTokenizer::new(value))
+    }
+
+    /// Classes of words replaced by placeholders before splitting lines, see `Mask`
+    pub fn with_masks(mut self, masks: &[Mask]) -> Self {
+        self.masks = Some(Masker::new(masks));
+        self
+    }
+
+    pub fn max_allowed_new_alternatives(&self) -> usize {
+        self.max_allowed_new_alternatives
+            .unwrap_or(DEFAULT_MAX_ALLOWED_NEW_ALTERNATIVES)
+    }
+
+    pub fn denote_optional(&self) -> &str {
+        self.denote_optional
+            .as_deref()
+            .unwrap_or(DEFAULT_DENOTE_OPTIONAL)
+    }
+
+    pub fn ignore_numeric_words(&self) -> bool {
+        self.ignore_numeric_words
+            .unwrap_or(DEFAULT_IGNORE_NUMERIC_WORDS)
+    }
+
+    pub fn ignore_first_columns(&self) -> usize {
+        self.ignore_first_columns
+            .unwrap_or(DEFAULT_IGNORE_FIRST_COLUMNS)
+    }
+
+    /// Falls back to `PunctuationTokenizer::default()`
+    pub fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        self.shared_tokenizer().0
+    }
+
+    fn shared_tokenizer(&self) -> SharedTokenizer {
+        self.tokenizer.clone().unwrap_or_default()
+    }
+
+    /// No class is masked by default
+    pub fn masks(&self) -> &[Mask] {
+        self.masks.as_ref().map_or(&[], |masks| masks.masks())
+    }
+
+    pub fn validate(&self) -> Result<(), LogmapError> {
+        if self
+            .tokenizer()
+            .delimiters()
+            .is_some_and(|delimiters| delimiters.is_empty())
+        {
+            return Err(LogmapError::InvalidConfig {
+                parameter: "word_delimiters",
+                value: String::new(),
+                reason: "cannot be empty",
+            });
+        }
+        if let Some(reason) = LogFiltersConfig::check_denote_optional(self.denote_optional(), None)
+        {
+            return Err(LogmapError::InvalidConfig {
+                parameter: "denote_optional",
+                value: self.denote_optional().to_string(),
+                reason,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Validate parameters and create empty `LogFilters` using them
+    pub fn build(self) -> Result<LogFilters, LogmapError> {
+        self.validate()?;
+
+        Ok(LogFilters {
+            filters: Vec::new(),
+            next_filter_id: 0,
+            filter_indexes: HashMap::new(),
+            words_hash: HashMap::new(),
+            max_allowed_new_alternatives: self.max_allowed_new_alternatives(),
+            denote_optional: self.denote_optional().to_string(),
+            ignore_numeric_words: self.ignore_numeric_words(),
+            ignore_first_columns: self.ignore_first_columns(),
+            tokenizer: self.shared_tokenizer(),
+            masks: self.masks.unwrap_or_default(),
+            comment: None,
+            rules: Vec::new(),
+        })
+    }
+
+    /// Apply tokenizer `settings` loaded from a filter file, see `LogFilters::tokenizer_settings`
+    ///
+    /// Files written before `kind` was stored were split by `PunctuationTokenizer`.
+    fn with_tokenizer_settings(
+        mut self,
+        settings: &[(String, String)],
+    ) -> Result<Self, LogmapError> {
+        let mut kind = "punctuation";
+        let mut tokenizer_settings = Vec::new();
+        for (setting, value) in settings {
+            match setting.as_str() {
+                "kind" => kind = value,
+                "masks" => {
+                    let masks =
+                        value
+                            .parse::<Masker>()
+                            .map_err(|_| LogmapError::UnsupportedTokenizer {
+                                path: None,
+                                setting: setting.clone(),
+                                value: value.clone(),
+                                expected: None,
+                            })?;
+                    self.masks = Some(masks);
+                }
+                "numeric_symbols" => {
+                    let expected: String = NUMERIC_SYMBOLS.iter().collect();
+                    if *value != expected {
+                        return Err(LogmapError::UnsupportedTokenizer {
+                            path: None,
+                            setting: setting.clone(),
+                            value: value.clone(),
+                            expected: Some(expected),
+                        });
+                    }
+                }
+                _ => tokenizer_settings.push((setting.clone(), value.clone())),
+            }
+        }
+        let tokenizer = tokenizer::from_settings(kind, &tokenizer_settings)?;
+        self.tokenizer = Some(SharedTokenizer(Arc::from(tokenizer)));
+
+        Ok(self)
+    }
+
+    /// Returns reason why `denote_optional` can't mark optional columns, if any
+    ///
+    /// Version 1 files don't escape words, there the marker must also consist of
+    /// `word_delimiters` only so that no word is ever read as the marker.
+    fn check_denote_optional(
+        denote_optional: &str,
+        word_delimiters: Option<&str>,
+    ) -> Option<&'static str> {
+        if denote_optional.is_empty() {
+            return Some("cannot be empty");
+        }
+        if denote_optional
+            .chars()
+            .any(|c| c.is_whitespace() || LogFilters::is_escaped_char(c))
+        {
+            return Some("cannot contain whitespace, `[`, `]`, `,` or `\\`");
+        }
+        // below must never land as word alternative
+        if word_delimiters.is_some_and(|word_delimiters| {
+            !denote_optional.chars().all(|c| word_delimiters.contains(c))
+        }) {
+            return Some("must consist of word separators only");
+        }
+
+        None
+    }
+}
+
+/// Tokenizer shared by `LogFilters` and their clones, compared by `kind` and `settings`
+///
+/// Serialized as map of settings holding `kind` as well.
+#[derive(Clone, Debug)]
+struct SharedTokenizer(Arc<dyn Tokenizer>);
+
+impl Default for SharedTokenizer {
+    fn default() -> Self {
+        SharedTokenizer(Arc::new(PunctuationTokenizer::default()))
+    }
+}
+
+impl PartialEq for SharedTokenizer {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.kind() == other.0.kind() && self.0.settings() == other.0.settings()
+    }
+}
+
+impl fmt::Display for SharedTokenizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let settings: Vec<String> = self
+            .0
+            .settings()
+            .iter()
+            .map(|(setting, value)| format!("{}={:?}", setting, value))
+            .collect();
+        if settings.is_empty() {
+            write!(f, "{}", self.0.kind())
+        } else {
+            write!(f, "{}({})", self.0.kind(), settings.join(", "))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for SharedTokenizer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut settings: BTreeMap<String, String> = self.0.settings().into_iter().collect();
+        settings.insert("kind".to_string(), self.0.kind().to_string());
+        settings.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for SharedTokenizer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut settings = BTreeMap::<String, String>::deserialize(deserializer)?;
+        let kind = settings
+            .remove("kind")
+            .unwrap_or_else(|| "punctuation".to_string());
+        let settings: Vec<(String, String)> = settings.into_iter().collect();
+        match tokenizer::from_settings(&kind, &settings) {
+            Ok(tokenizer) => Ok(SharedTokenizer(Arc::from(tokenizer))),
+            Err(err) => Err(serde::de::Error::custom(err)),
+        }
+    }
+}
+
+/// Parameter whose value loaded from a filter file differs from an explicit override
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigConflict {
+    pub parameter: &'static str,
+    pub loaded: String,
+    pub requested: String,
+    /// `true` if requested value replaced the loaded one
+    pub overridden: bool,
+}
+
+impl fmt::Display for ConfigConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let used = if self.overridden {
+            &self.requested
+        } else {
+            &self.loaded
+        };
+        write!(
+            f,
+            "`{}` was loaded as {} but {} was requested, using {}",
+            self.parameter, self.loaded, self.requested, used
+        )
+    }
+}
+
+/// Stable identifier of a filter
+///
+/// Unlike position of a filter it is saved with the filter and does not change
+/// when other filters are added, removed or reordered.
+pub type FilterId = u64;
+
+/// Change made to filters by `LogFilters::learn_line`
+#[derive(Clone, Debug, PartialEq)]
+pub enum LearnOutcome {
+    /// Line had no words left to learn from
+    Skipped,
+    /// No filter matched, new filter was added
+    NewFilter { id: FilterId },
+    /// Matching filter was extended
+    Extended {
+        id: FilterId,
+        /// `(column index, word)` pairs of new word alternatives
+        added_alternatives: Vec<(usize, String)>,
+        /// Indexes of columns which became optional (including newly inserted ones)
+        new_optional_columns: Vec<usize>,
+    },
+    /// Matching filter already covered the line
+    Unchanged { id: FilterId },
+}
+
+impl fmt::Display for LearnOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LearnOutcome::Skipped => write!(f, "skipped"),
+            LearnOutcome::NewFilter { id } => write!(f, "new filter {}", id),
+            LearnOutcome::Extended {
+                id,
+                ref added_alternatives,
+                ref new_optional_columns,
+            } => {
+                write!(f, "extended filter {}", id)?;
+                for &(column_index, ref word) in added_alternatives {
+                    write!(f, "; column {} += {}", column_index, word)?;
+                }
+                for column_index in new_optional_columns {
+                    write!(f, "; column {} optional", column_index)?;
+                }
+                Ok(())
+            }
+            LearnOutcome::Unchanged { id } => write!(f, "unchanged filter {}", id),
+        }
+    }
+}
+
+/// `(column index, word)` pairs
+type ColumnWords = Vec<(usize, String)>;
+
+/// Difference between two sets of filters, see `LogFilters::diff`
+#[derive(Clone, Debug, PartialEq)]
+pub enum FilterChange {
+    /// Filter found only in new filters
+    Added(Filter),
+    /// Filter found only in old filters
+    Removed(Filter),
+    /// Filter found in both, with different columns
+    Changed {
+        old: Filter,
+        new: Filter,
+        /// `(column index in new filter, word)` pairs of new word alternatives
+        added_alternatives: Vec<(usize, String)>,
+        /// `(column index in old filter, word)` pairs of word alternatives no longer accepted
+        removed_alternatives: Vec<(usize, String)>,
+        /// Indexes of new filter columns which became optional (including newly inserted ones)
+        new_optional_columns: Vec<usize>,
+    },
+}
+
+impl fmt::Display for FilterChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FilterChange::Added(ref filter) => write!(f, "added filter {}", filter.id),
+            FilterChange::Removed(ref filter) => write!(f, "removed filter {}", filter.id),
+            FilterChange::Changed {
+                ref old,
+                ref new,
+                ref added_alternatives,
+                ref removed_alternatives,
+                ref new_optional_columns,
+            } => {
+                write!(f, "changed filter {}", new.id)?;
+                if old.id != new.id {
+                    write!(f, " (was {})", old.id)?;
+                }
+                for &(column_index, ref word) in added_alternatives {
+                    write!(f, "; column {} += {}", column_index, word)?;
+                }
+                for column_index in new_optional_columns {
+                    write!(f, "; column {} optional", column_index)?;
+                }
+                for &(column_index, ref word) in removed_alternatives {
+                    write!(f, "; old column {} -= {}", column_index, word)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Result of matching a log line against known filters, see `LogFilters::match_line`
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineMatch {
+    /// Identifier of the best matching filter
+    pub filter_id: FilterId,
+    /// Index of the best matching filter
+    pub filter_index: usize,
+    /// Number of words matching filter columns in order
+    pub score: usize,
+    /// Words of the line after ignored columns and numeric words were dropped
+    pub words: Vec<String>,
+    /// `(word index, column index)` pairs of words found in the filter
+    pub matched_columns: Vec<(usize, usize)>,
+    /// Indexes of words not found in the filter, treated as new alternatives
+    pub new_alternatives: Vec<usize>,
+}
+
+/// Single column of a filter, matching any of its word alternatives
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Column {
+    alternatives: Vec<String>,
+    optional: bool,
+}
+
+impl Column {
+    fn new(word: &str) -> Self {
+        Column {
+            alternatives: vec![word.to_string()],
+            optional: false,
+        }
+    }
+
+    /// Words accepted in this column, in order they were learned
+    pub fn alternatives(&self) -> &[String] {
+        &self.alternatives
+    }
+
+    /// Optional column may be missing from a matching line
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.alternatives.iter().any(|w| w == word)
+    }
+}
+
+/// Filter describing one kind of log line as a sequence of columns
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Filter {
+    id: FilterId,
+    columns: Vec<Column>,
+    /// Kept only to be saved together with the filter, see `LogFilters::set_note`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    note: Option<String>,
+}
+
+impl Filter {
+    pub fn id(&self) -> FilterId {
+        self.id
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Note written above the filter in filter files, may span multiple lines
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+}
+
+/// Hand-written rule accepting log lines next to learned filters, see `LogFilters::add_rule`
+///
+/// Rules are never changed by learning and are not saved together with filters.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    kind: RuleKind,
+    /// Pattern the rule was built from
+    pattern: String,
+}
+
+#[derive(Clone, Debug)]
+enum RuleKind {
+    Regex(Regex),
+    /// Words of the pattern, including `TEMPLATE_ANY_WORD` and `TEMPLATE_ANY_WORDS`,
+    /// `None` until `LogFilters::add_rule` splits the pattern with its tokenizer
+    Template(Option<Vec<String>>),
+}
+
+impl Rule {
+    /// Rule accepting lines matched anywhere by regular expression `pattern`
+    ///
+    /// Expression is checked against the whole line, anchor it with `^` and `$` to match
+    /// complete lines, e.g. ones printed by `LogFilters::filter_to_regex`.
+    pub fn regex(pattern: &str) -> Result<Self, LogmapError> {
+        let regex = Regex::new(pattern).map_err(|err| LogmapError::InvalidRule {
+            rule: pattern.to_string(),
+            reason: err.to_string(),
+        })?;
+
+        Ok(Rule {
+            kind: RuleKind::Regex(regex),
+            pattern: pattern.to_string(),
+        })
+    }
+
+    /// Rule accepting lines with the same words as `template`
+    ///
+    /// Template is masked and split into words the same way as lines by filters it is added to
+    /// and compared with line words left after dropping ignored first columns and numeric words.
+    /// `<*>` stands for any single word and `<**>` for any number of words,
+    /// e.g. `CRON <**> session opened for user <*>`. Words are unknown until the rule is added.
+    pub fn template(template: &str) -> Result<Self, LogmapError> {
+        if template.trim().is_empty() {
+            return Err(LogmapError::InvalidRule {
+                rule: template.to_string(),
+                reason: "template has no words".to_string(),
+            });
+        }
+
+        Ok(Rule {
+            kind: RuleKind::Template(None),
+            pattern: template.to_string(),
+        })
+    }
+
+    /// Pattern the rule was built from
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Check rule against `log_line` split into `words`
+    fn is_match(&self, log_line: &str, words: &[String]) -> bool {
+        match self.kind {
+            RuleKind::Regex(ref regex) => regex.is_match(log_line),
+            RuleKind::Template(Some(ref template)) => Rule::template_matches(template, words),
+            // Rules are checked only once added to filters
+            RuleKind::Template(None) => false,
+        }
+    }
+
+    /// Match `words` against `template` words, backtracking to the last `TEMPLATE_ANY_WORDS`
+    fn template_matches(template: &[String], words: &[String]) -> bool {
+        let mut t = 0;
+        let mut w = 0;
+        // Template and word position right after the last `TEMPLATE_ANY_WORDS` seen
+        let mut any_words: Option<(usize, usize)> = None;
+        while w < words.len() {
+            if t < template.len() && template[t] == TEMPLATE_ANY_WORDS {
+                t += 1;
+                any_words = Some((t, w));
+            } else if t < template.len()
+                && (template[t] == TEMPLATE_ANY_WORD || template[t] == words[w])
+            {
+                t += 1;
+                w += 1;
+            } else if let Some((any_t, any_w)) = any_words {
+                // Let `TEMPLATE_ANY_WORDS` take one more word and try again
+                t = any_t;
+                w = any_w + 1;
+                any_words = Some((any_t, w));
+            } else {
+                return false;
+            }
+        }
+
+        template[t..].iter().all(|word| word == TEMPLATE_ANY_WORDS)
+    }
+}
+
+/// Same as a line of rules file, see `LogFilters::load_rules_from`
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            RuleKind::Regex(_) => write!(f, "regex: {}", self.pattern),
+            RuleKind::Template(_) => write!(f, "template: {}", self.pattern),
+        }
+    }
+}
+
+/// With `serde` feature `words_hash` is not serialized, it is rebuilt when deserializing
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "SerializedLogFilters")
+)]
+pub struct LogFilters {
+    /// Each `filters` element stores columns of individual words variations
+    /// filters (Vec) - collection of all log lines
+    ///    |
+    ///    |- filter (Filter) - identifier and collection of columns within log line
+    ///          |
+    ///          |- column (Column) - collection of words within word variation
+    ///                   |
+    ///                   |- word1 (String)
+    ///                   |- word2 (String)
+    filters: Vec<Filter>,
+    /// Identifier given to the next new filter
+    next_filter_id: FilterId,
+    /// Index in `filters` of each filter identifier
+    #[cfg_attr(feature = "serde", serde(skip))]
+    filter_indexes: HashMap<FilterId, usize>,
+    /// Each unique word from `filters` gets its own key
+    /// Each key stores references to lines containing the key
+    #[cfg_attr(feature = "serde", serde(skip))]
+    words_hash: HashMap<String, Vec<usize>>,
+    /// Maximum allowed new alternatives when analysing any new line
+    max_allowed_new_alternatives: usize,
+    /// Marks optional columns in the text representation of filters
+    denote_optional: String,
+    /// Should words that contain only numbers be ignored
+    ignore_numeric_words: bool,
+    /// Drop first columns before analysing
+    ignore_first_columns: usize,
+    /// Splits log lines into words
+    tokenizer: SharedTokenizer,
+    /// Replaces variable words before lines are split
+    masks: Masker,
+    /// Comment of the whole filter file, see `set_comment`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    comment: Option<String>,
+    /// Hand-written rules, see `add_rule`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rules: Vec<Rule>,
+}
+
+impl Default for LogFilters {
+    fn default() -> Self {
+        LogFilters::new()
+    }
+}
+
+impl LogFilters {
+    pub fn new() -> Self {
+        LogFiltersConfig::new()
+            .build()
+            .expect("Default configuration must be valid")
+    }
+
+    /// Current parameters, all explicitly set
+    pub fn config(&self) -> LogFiltersConfig {
+        let mut config = LogFiltersConfig::new()
+            .with_max_allowed_new_alternatives(self.max_allowed_new_alternatives)
+            .with_denote_optional(&self.denote_optional)
+            .with_ignore_numeric_words(self.ignore_numeric_words)
+            .with_ignore_first_columns(self.ignore_first_columns);
+        config.tokenizer = Some(self.tokenizer.clone());
+        config.masks = Some(self.masks.clone());
+        config
+    }
+
+    /// Replace parameters with values explicitly set in `overrides`
+    ///
+    /// Explicitly set values win over the current (e.g. loaded from file) ones, except for
+    /// `denote_optional` which is how existing filters mark optional columns and is kept.
+    /// Every value that differs from the current one is reported as `ConfigConflict`.
+    pub fn apply_overrides(
+        &mut self,
+        overrides: &LogFiltersConfig,
+    ) -> Result<Vec<ConfigConflict>, LogmapError> {
+        overrides.validate()?;
+        let mut conflicts = Vec::new();
+
+        if let Some(value) = overrides.max_allowed_new_alternatives {
+            if value != self.max_allowed_new_alternatives {
+                conflicts.push(ConfigConflict {
+                    parameter: "max_allowed_new_alternatives",
+                    loaded: self.max_allowed_new_alternatives.to_string(),
+                    requested: value.to_string(),
+                    overridden: true,
+                });
+                self.max_allowed_new_alternatives = value;
+            }
+        }
+        if let Some(ref value) = overrides.denote_optional {
+            if *value != self.denote_optional {
+                conflicts.push(ConfigConflict {
+                    parameter: "denote_optional",
+                    loaded: self.denote_optional.clone(),
+                    requested: value.clone(),
+                    overridden: false,
+                });
+            }
+        }
+        if let Some(value) = overrides.ignore_numeric_words {
+            if value != self.ignore_numeric_words {
+                conflicts.push(ConfigConflict {
+                    parameter: "ignore_numeric_words",
+                    loaded: self.ignore_numeric_words.to_string(),
+                    requested: value.to_string(),
+                    overridden: true,
+                });
+                self.ignore_numeric_words = value;
+            }
+        }
+        if let Some(value) = overrides.ignore_first_columns {
+            if value != self.ignore_first_columns {
+                conflicts.push(ConfigConflict {
+                    parameter: "ignore_first_columns",
+                    loaded: self.ignore_first_columns.to_string(),
+                    requested: value.to_string(),
+                    overridden: true,
+                });
+                self.ignore_first_columns = value;
+            }
+        }
+        // Filter words were split by loaded tokenizer, see `tokenizer_settings`
+        if let Some(ref value) = overrides.tokenizer {
+            if *value != self.tokenizer {
+                conflicts.push(ConfigConflict {
+                    parameter: "tokenizer",
+                    loaded: self.tokenizer.to_string(),
+                    requested: value.to_string(),
+                    overridden: false,
+                });
+            }
+        }
+        if let Some(ref value) = overrides.masks {
+            if *value != self.masks {
+                conflicts.push(ConfigConflict {
+                    parameter: "masks",
+                    loaded: format!("{:?}", self.masks.to_string()),
+                    requested: format!("{:?}", value.to_string()),
+                    overridden: false,
+                });
+            }
+        }
+
+        Ok(conflicts)
+    }
+
+    /// Save filters together with parameters under `path`
+    pub fn save(&self, path: &Path) -> Result<(), LogmapError> {
+        self.save_as(path, FileFormat::Text)
+    }
+
+    /// Save filters together with parameters under `path` using given `format`
+    ///
+    /// Filters are written to a temporary file next to `path` which then replaces `path`,
+    /// so `path` holds either previous or new filters even if saving fails half way.
+    /// File is compressed when `path` ends with `.gz` or `.zst`, see `Compression::from_path`.
+    pub fn save_as(&self, path: &Path, format: FileFormat) -> Result<(), LogmapError> {
+        self.save_replacing(path, format, false)
+    }
+
+    /// Same as `save_as`, previous content of `path` is kept under `path` with `.bak` appended
+    pub fn save_with_backup(&self, path: &Path, format: FileFormat) -> Result<(), LogmapError> {
+        self.save_replacing(path, format, true)
+    }
+
+    fn save_replacing(
+        &self,
+        path: &Path,
+        format: FileFormat,
+        keep_backup: bool,
+    ) -> Result<(), LogmapError> {
+        write_atomically(path, keep_backup, |file| {
+            match Compression::from_path(path) {
+                Compression::None => self.save_format_to(file, format),
+                #[cfg(feature = "gzip")]
+                Compression::Gzip => {
+                    let mut encoder =
+                        flate2::write::GzEncoder::new(file, flate2::Compression::default());
+                    self.save_format_to(&mut encoder, format)?;
+                    encoder.finish()?;
+                    Ok(())
+                }
+                #[cfg(feature = "zstd")]
+                Compression::Zstd => {
+                    let mut encoder = zstd::Encoder::new(file, 0)?;
+                    self.save_format_to(&mut encoder, format)?;
+                    encoder.finish()?;
+                    Ok(())
+                }
+                #[allow(unreachable_patterns)]
+                compression => Err(LogmapError::UnsupportedCompression {
+                    path: None,
+                    compression: compression.name(),
+                }),
+            }
+        })
+        .map_err(|e| e.with_path(path))
+    }
+
+    fn save_format_to<W: Write>(&self, writer: W, format: FileFormat) -> Result<(), LogmapError> {
+        match format {
+            FileFormat::Text => self.save_to(writer),
+            #[cfg(feature = "json")]
+            FileFormat::Json => self.save_json_to(writer),
+            FileFormat::Binary => self.save_binary_to(writer),
+        }
+    }
+
+    /// Write filters together with parameters to `writer`
+    ///
+    /// Output always uses the newest format: header line with `FORMAT_VERSION`,
+    /// `key=value` parameters, empty line and then one filter per line.
+    pub fn save_to<W: Write>(&self, mut writer: W) -> Result<(), LogmapError> {
+        let mut log_filters_str = format!("{}{}\n", FORMAT_HEADER, FORMAT_VERSION);
+        let parameters = [
+            (
+                "max_allowed_new_alternatives",
+                self.max_allowed_new_alternatives.to_string(),
+            ),
+            ("denote_optional", self.denote_optional.clone()),
+            (
+                "ignore_numeric_words",
+                self.ignore_numeric_words.to_string(),
+            ),
+            (
+                "ignore_first_columns",
+                self.ignore_first_columns.to_string(),
+            ),
+            ("next_filter_id", self.next_filter_id.to_string()),
+        ];
+        for (key, value) in parameters.iter() {
+            log_filters_str += &format!("{}={}\n", key, value);
+        }
+        for (setting, value) in self.tokenizer_settings() {
+            log_filters_str += &format!(
+                "{}{}={}\n",
+                TOKENIZER_PARAMETER_PREFIX,
+                setting,
+                escape_parameter(&value)
+            );
+        }
+        log_filters_str += "\n";
+        // Blank line keeps file comment apart from note of the first filter
+        if let Some(ref comment) = self.comment {
+            log_filters_str += &comment_lines(comment);
+            log_filters_str += "\n";
+        }
+        for filter in &self.filters {
+            if let Some(ref note) = filter.note {
+                log_filters_str += &comment_lines(note);
+            }
+            log_filters_str += &format!("{}:{}\n", filter.id, self.filter_to_string(filter));
+        }
+
+        writer.write_all(log_filters_str.as_bytes())?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Load filters and parameters previously saved under `path` in any `FileFormat`
+    ///
+    /// Format is recognised by file content, see `FileFormat::detect`.
+    pub fn load(path: &Path) -> Result<Self, LogmapError> {
+        LogFilters::load_with(path, None)
+    }
+
+    /// Load filters and parameters previously saved under `path` using given `format`
+    ///
+    /// Compressed files are recognised by their content, whatever their name is.
+    pub fn load_as(path: &Path, format: FileFormat) -> Result<Self, LogmapError> {
+        LogFilters::load_with(path, Some(format))
+    }
+
+    /// Load from `path` in `format`, detected from content if `None`
+    fn load_with(path: &Path, format: Option<FileFormat>) -> Result<Self, LogmapError> {
+        File::open(path)
+            .map_err(LogmapError::from)
+            .and_then(|file| {
+                let mut reader = io::BufReader::new(file);
+                match Compression::detect(reader.fill_buf()?) {
+                    Compression::None => LogFilters::load_detected_from(reader, format),
+                    #[cfg(feature = "gzip")]
+                    Compression::Gzip => LogFilters::load_detected_from(
+                        flate2::bufread::MultiGzDecoder::new(reader),
+                        format,
+                    ),
+                    #[cfg(feature = "zstd")]
+                    Compression::Zstd => {
+                        LogFilters::load_detected_from(zstd::Decoder::with_buffer(reader)?, format)
+                    }
+                    #[allow(unreachable_patterns)]
+                    compression => Err(LogmapError::UnsupportedCompression {
+                        path: None,
+                        compression: compression.name(),
+                    }),
+                }
+            })
+            .map_err(|e| e.with_path(path))
+    }
+
+    /// Load from `reader` in `format`, detected from content if `None`
+    fn load_detected_from<R: Read>(
+        mut reader: R,
+        format: Option<FileFormat>,
+    ) -> Result<Self, LogmapError> {
+        match format {
+            Some(format) => LogFilters::load_format_from(reader, format),
+            None => {
+                // Longest start told apart by `FileFormat::detect`, read back before loading
+                let mut start = Vec::with_capacity(BINARY_MAGIC.len());
+                (&mut reader)
+                    .take(BINARY_MAGIC.len() as u64)
+                    .read_to_end(&mut start)?;
+                let format = FileFormat::detect(&start);
+                LogFilters::load_format_from(io::Cursor::new(start).chain(reader), format)
+            }
+        }
+    }
+
+    fn load_format_from<R: Read>(reader: R, format: FileFormat) -> Result<Self, LogmapError> {
+        match format {
+            FileFormat::Text => LogFilters::load_from(reader),
+            #[cfg(feature = "json")]
+            FileFormat::Json => LogFilters::load_json_from(reader),
+            FileFormat::Binary => LogFilters::load_binary_from(reader),
+        }
+    }
+
+    /// Write filters together with parameters to `writer` as JSON document
+    ///
+    /// Parameters are stored in `header` object next to `format_version`,
+    /// `filters` holds `id` and `columns` of each filter,
+    /// each column holds its word `alternatives` and `optional` flag.
+    #[cfg(feature = "json")]
+    pub fn save_json_to<W: Write>(&self, mut writer: W) -> Result<(), LogmapError> {
+        let document = JsonDocument {
+            header: JsonHeader {
+                format_version: JSON_FORMAT_VERSION,
+                max_allowed_new_alternatives: self.max_allowed_new_alternatives,
+                denote_optional: self.denote_optional.clone(),
+                ignore_numeric_words: self.ignore_numeric_words,
+                ignore_first_columns: self.ignore_first_columns,
+                next_filter_id: self.next_filter_id,
+                tokenizer: self.tokenizer_settings().into_iter().collect(),
+            },
+            comment: self.comment.clone(),
+            filters: &self.filters,
+        };
+        serde_json::to_writer_pretty(&mut writer, &document).map_err(io::Error::from)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Read filters and parameters previously written by `save_json_to`
+    ///
+    /// Unknown `header` entries are ignored, filters are validated the same way as with `serde` feature.
+    #[cfg(feature = "json")]
+    pub fn load_json_from<R: Read>(reader: R) -> Result<Self, LogmapError> {
+        let document: JsonDocument<Vec<Filter>> = serde_json::from_reader(reader).map_err(|e| {
+            if e.is_io() {
+                LogmapError::io(io::Error::from(e))
+            } else {
+                LogmapError::Json {
+                    path: None,
+                    source: e,
+                }
+            }
+        })?;
+        let header = document.header;
+        if header.format_version != JSON_FORMAT_VERSION {
+            return Err(LogmapError::UnsupportedVersion {
+                path: None,
+                version: u64::from(header.format_version),
+                supported: JSON_FORMAT_VERSION,
+            });
+        }
+
+        let tokenizer_settings: Vec<(String, String)> = header.tokenizer.into_iter().collect();
+        let config = LogFiltersConfig::new().with_tokenizer_settings(&tokenizer_settings)?;
+
+        LogFilters::try_from(SerializedLogFilters {
+            filters: document.filters,
+            comment: document.comment,
+            next_filter_id: header.next_filter_id,
+            max_allowed_new_alternatives: header.max_allowed_new_alternatives,
+            denote_optional: header.denote_optional,
+            ignore_numeric_words: header.ignore_numeric_words,
+            ignore_first_columns: header.ignore_first_columns,
+            tokenizer: config.shared_tokenizer(),
+            masks: config.masks.unwrap_or_default(),
+        })
+    }
+
+    /// Read filters and parameters previously written by `save_to`
+    ///
+    /// Files written before the format got versioned (version 1) are read as well,
+    /// writing them back with `save_to` upgrades them to `FORMAT_VERSION`.
+    pub fn load_from<R: Read>(mut reader: R) -> Result<Self, LogmapError> {
+        let mut log_filters_str = String::new();
+        reader.read_to_string(&mut log_filters_str)?;
+        let log_filters_lines: Vec<&str> = log_filters_str.split('\n').collect();
+
+        let (mut log_filters, header_lines) = if log_filters_lines[0].starts_with(FORMAT_HEADER) {
+            LogFilters::load_parameters(&log_filters_lines)?
+        } else {
+            (
+                LogFilters::load_v1_parameters(&log_filters_lines)?,
+                V1_HEADER_LINES,
+            )
+        };
+        // Words in version 1 files were written as they are
+        let unescape = header_lines != V1_HEADER_LINES;
+        log_filters.from_str_lines(
+            &log_filters_lines[header_lines..],
+            header_lines + 1,
+            unescape,
+        )?;
+
+        Ok(log_filters)
+    }
+
+    /// Write filters together with parameters and index of words to `writer` in binary format
+    ///
+    /// Every distinct word is stored once in a word table and referenced by its position,
+    /// `words_hash` is stored as well so loading does not need to rebuild it.
+    /// All integers are written as LEB128 variable length values, strings are prefixed with their length.
+    pub fn save_binary_to<W: Write>(&self, mut writer: W) -> Result<(), LogmapError> {
+        let mut words: Vec<&str> = Vec::new();
+        let mut word_indexes: HashMap<&str, usize> = HashMap::new();
+        for filter in &self.filters {
+            for column in &filter.columns {
+                for word in &column.alternatives {
+                    word_indexes.entry(word).or_insert_with(|| {
+                        words.push(word);
+                        words.len() - 1
+                    });
+                }
+            }
+        }
+
+        let mut buffer = BINARY_MAGIC.to_vec();
+        write_varint(&mut buffer, u64::from(BINARY_FORMAT_VERSION));
+        write_varint(&mut buffer, self.max_allowed_new_alternatives as u64);
+        write_bytes(&mut buffer, self.denote_optional.as_bytes());
+        buffer.push(self.ignore_numeric_words as u8);
+        write_varint(&mut buffer, self.ignore_first_columns as u64);
+        write_varint(&mut buffer, self.next_filter_id);
+        write_optional_string(&mut buffer, &self.comment);
+        let tokenizer_settings = self.tokenizer_settings();
+        write_varint(&mut buffer, tokenizer_settings.len() as u64);
+        for (setting, value) in &tokenizer_settings {
+            write_bytes(&mut buffer, setting.as_bytes());
+            write_bytes(&mut buffer, value.as_bytes());
+        }
+
+        write_varint(&mut buffer, words.len() as u64);
+        for word in &words {
+            write_bytes(&mut buffer, word.as_bytes());
+        }
+
+        write_varint(&mut buffer, self.filters.len() as u64);
+        for filter in &self.filters {
+            write_varint(&mut buffer, filter.id);
+            write_varint(&mut buffer, filter.columns.len() as u64);
+            for column in &filter.columns {
+                buffer.push(column.optional as u8);
+                write_varint(&mut buffer, column.alternatives.len() as u64);
+                for word in &column.alternatives {
+                    write_varint(&mut buffer, word_indexes[word.as_str()] as u64);
+                }
+            }
+            write_optional_string(&mut buffer, &filter.note);
+        }
+
+        // Same order as word table so that output does not depend on `HashMap` order
+        let mut index: Vec<(usize, &Vec<usize>)> = self
+            .words_hash
+            .iter()
+            .map(|(word, filter_indexes)| (word_indexes[word.as_str()], filter_indexes))
+            .collect();
+        index.sort();
+        write_varint(&mut buffer, index.len() as u64);
+        for (word_index, filter_indexes) in index {
+            write_varint(&mut buffer, word_index as u64);
+            write_varint(&mut buffer, filter_indexes.len() as u64);
+            for &filter_index in filter_indexes {
+                write_varint(&mut buffer, filter_index as u64);
+            }
+        }
+
+        writer.write_all(&buffer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Read filters, parameters and index of words previously written by `save_binary_to`
+    pub fn load_binary_from<R: Read>(mut reader: R) -> Result<Self, LogmapError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        if !bytes.starts_with(BINARY_MAGIC) {
+            return Err(LogmapError::CorruptBinary {
+                path: None,
+                offset: 0,
+                reason: "not a binary filter file",
+            });
+        }
+        let mut reader = BinaryReader {
+            bytes: &bytes,
+            offset: BINARY_MAGIC.len(),
+        };
+
+        let version = reader.varint()?;
+        if version != u64::from(BINARY_FORMAT_VERSION) {
+            return Err(LogmapError::UnsupportedVersion {
+                path: None,
+                version,
+                supported: BINARY_FORMAT_VERSION,
+            });
+        }
+        let max_allowed_new_alternatives = reader.varint()? as usize;
+        let denote_optional = reader.string()?;
+        let ignore_numeric_words = match reader.byte()? {
+            0 => false,
+            1 => true,
+            _ => return Err(reader.corrupt("invalid flag")),
+        };
+        let ignore_first_columns = reader.varint()? as usize;
+        let config = LogFiltersConfig::new()
+            .with_max_allowed_new_alternatives(max_allowed_new_alternatives)
+            .with_denote_optional(&denote_optional)
+            .with_ignore_numeric_words(ignore_numeric_words)
+            .with_ignore_first_columns(ignore_first_columns);
+        let next_filter_id = reader.varint()?;
+        let comment = reader.optional_string()?;
+        let mut tokenizer_settings = Vec::new();
+        for _ in 0..reader.count()? {
+            let setting = reader.string()?;
+            let value = reader.string()?;
+            tokenizer_settings.push((setting, value));
+        }
+        let mut log_filters = config
+            .with_tokenizer_settings(&tokenizer_settings)?
+            .build()?;
+        log_filters.next_filter_id = next_filter_id;
+        log_filters.comment = comment;
+
+        let words_count = reader.count()?;
+        let mut words = Vec::with_capacity(words_count);
+        for _ in 0..words_count {
+            words.push(reader.string()?);
+        }
+
+        let filters_count = reader.count()?;
+        let mut ids = HashSet::with_capacity(filters_count);
+        log_filters.filters.reserve(filters_count);
+        for _ in 0..filters_count {
+            let id = reader.varint()?;
+            if !ids.insert(id) || id >= log_filters.next_filter_id {
+                return Err(reader.corrupt("invalid filter identifier"));
+            }
+            let columns_count = reader.non_zero_count()?;
+            let mut columns = Vec::with_capacity(columns_count);
+            for _ in 0..columns_count {
+                let optional = match reader.byte()? {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(reader.corrupt("invalid flag")),
+                };
+                let alternatives_count = reader.non_zero_count()?;
+                let mut alternatives = Vec::with_capacity(alternatives_count);
+                for _ in 0..alternatives_count {
+                    alternatives.push(words[reader.index(words.len())?].clone());
+                }
+                columns.push(Column {
+                    alternatives,
+                    optional,
+                });
+            }
+            let note = reader.optional_string()?;
+            log_filters.push_filter(Filter { id, columns, note });
+        }
+
+        let index_count = reader.count()?;
+        log_filters.words_hash.reserve(index_count);
+        for _ in 0..index_count {
+            let word = &words[reader.index(words.len())?];
+            let filter_indexes_count = reader.non_zero_count()?;
+            let mut filter_indexes: Vec<usize> = Vec::with_capacity(filter_indexes_count);
+            for _ in 0..filter_indexes_count {
+                let filter_index = reader.index(filters_count)?;
+                if filter_indexes
+                    .last()
+                    .is_some_and(|&last| last >= filter_index)
+                {
+                    return Err(reader.corrupt("filter indexes are not sorted"));
+                }
+                filter_indexes.push(filter_index);
+            }
+            if log_filters
+                .words_hash
+                .insert(word.clone(), filter_indexes)
+                .is_some()
+            {
+                return Err(reader.corrupt("word indexed more than once"));
+            }
+        }
+        if reader.offset != bytes.len() {
+            return Err(reader.corrupt("unexpected data after index"));
+        }
+
+        Ok(log_filters)
+    }
+
+    /// Parse header of a versioned file, returns number of lines it takes
+    ///
+    /// Parameters missing from the header fall back to defaults.
+    /// Unknown parameters are skipped so that parameters added later do not break this reader,
+    /// changes that must not be ignored come with a new `FORMAT_VERSION`.
+    fn load_parameters(log_filters_lines: &[&str]) -> Result<(Self, usize), LogmapError> {
+        let version = &log_filters_lines[0][FORMAT_HEADER.len()..];
+        match version.parse::<u64>() {
+            Ok(version) if version == u64::from(FORMAT_VERSION) => {}
+            Ok(version) => {
+                return Err(LogmapError::UnsupportedVersion {
+                    path: None,
+                    version,
+                    supported: FORMAT_VERSION,
+                })
+            }
+            Err(_) => {
+                return Err(LogmapError::CorruptHeader {
+                    path: None,
+                    line: 1,
+                    found: log_filters_lines[0].to_string(),
+                })
+            }
+        }
+
+        let mut config = LogFiltersConfig::new();
+        let mut next_filter_id = 0;
+        let mut tokenizer_settings = Vec::new();
+        let mut keys_seen: Vec<&str> = Vec::new();
+        for (line_index, line) in log_filters_lines.iter().enumerate().skip(1) {
+            if line.is_empty() {
+                let mut log_filters = config
+                    .with_tokenizer_settings(&tokenizer_settings)?
+                    .build()?;
+                log_filters.next_filter_id = next_filter_id;
+                return Ok((log_filters, line_index + 1));
+            }
+            let bad_parameter = || LogmapError::BadParameter {
+                path: None,
+                line: line_index + 1,
+                value: line.to_string(),
+            };
+            let (key, value) = match line.split_once('=') {
+                Some(key_and_value) => key_and_value,
+                None => return Err(bad_parameter()),
+            };
+            if keys_seen.contains(&key) {
+                return Err(bad_parameter());
+            }
+            keys_seen.push(key);
+            match key {
+                "max_allowed_new_alternatives" => {
+                    let value = value.parse::<usize>().map_err(|_| bad_parameter())?;
+                    config = config.with_max_allowed_new_alternatives(value);
+                }
+                "denote_optional" => {
+                    if LogFiltersConfig::check_denote_optional(value, None).is_some() {
+                        return Err(bad_parameter());
+                    }
+                    config = config.with_denote_optional(value);
+                }
+                "ignore_numeric_words" => {
+                    let value = value.parse::<bool>().map_err(|_| bad_parameter())?;
+                    config = config.with_ignore_numeric_words(value);
+                }
+                "ignore_first_columns" => {
+                    let value = value.parse::<usize>().map_err(|_| bad_parameter())?;
+                    config = config.with_ignore_first_columns(value);
+                }
+                "next_filter_id" => {
+                    next_filter_id = value.parse::<FilterId>().map_err(|_| bad_parameter())?;
+                }
+                // Unknown tokenizer settings are rejected by `with_tokenizer_settings`,
+                // other unknown parameters are ignored
+                _ if key.starts_with(TOKENIZER_PARAMETER_PREFIX) => {
+                    let value = unescape_parameter(value).ok_or_else(bad_parameter)?;
+                    let setting = key[TOKENIZER_PARAMETER_PREFIX.len()..].to_string();
+                    tokenizer_settings.push((setting, value));
+                }
+                _ => {}
+            }
+        }
+
+        Err(LogmapError::CorruptHeader {
+            path: None,
+            line: log_filters_lines.len() + 1,
+            found: "end of file".to_string(),
+        })
+    }
+
+    /// Parse four positional parameters of a version 1 file
+    fn load_v1_parameters(log_filters_lines: &[&str]) -> Result<Self, LogmapError> {
+        if log_filters_lines.len() <= V1_HEADER_LINES {
+            return Err(LogmapError::CorruptHeader {
+                path: None,
+                line: log_filters_lines.len() + 1,
+                found: "end of file".to_string(),
+            });
+        }
+
+        let bad_parameter = |line: usize| LogmapError::BadParameter {
+            path: None,
+            line: line + 1,
+            value: log_filters_lines[line].to_string(),
+        };
+
+        let max_allowed_new_alternatives = log_filters_lines[0]
+            .parse::<usize>()
+            .map_err(|_| bad_parameter(0))?;
+
+        let denote_optional = log_filters_lines[1].to_string();
+        if LogFiltersConfig::check_denote_optional(&denote_optional, Some(DEFAULT_WORD_DELIMITERS))
+            .is_some()
+        {
+            return Err(LogmapError::CorruptHeader {
+                path: None,
+                line: 2,
+                found: denote_optional,
+            });
+        }
+
+        let ignore_numeric_words = log_filters_lines[2]
+            .parse::<bool>()
+            .map_err(|_| bad_parameter(2))?;
+
+        let ignore_first_columns = log_filters_lines[3]
+            .parse::<usize>()
+            .map_err(|_| bad_parameter(3))?;
+
+        Ok(LogFilters {
+            filters: Vec::new(),
+            next_filter_id: 0,
+            filter_indexes: HashMap::new(),
+            words_hash: HashMap::new(),
+            max_allowed_new_alternatives,
+            denote_optional,
+            ignore_numeric_words,
+            ignore_first_columns,
+            tokenizer: SharedTokenizer::default(),
+            masks: Masker::default(),
+            comment: None,
+            rules: Vec::new(),
+        })
+    }
+
+    /// Parse filters from their `to_string` representation
+    ///
+    /// Each filter may be prefixed with its identifier followed by `:`, as written by `save_to`.
+    /// Filters without identifier get a new one.
+    /// `first_line_no` is the line number of `log_filters_lines[0]` and is only used for error reporting.
+    /// `unescape` is `false` only for files written before words got escaped.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_str_lines(
+        &mut self,
+        log_filters_lines: &[&str],
+        first_line_no: usize,
+        unescape: bool,
+    ) -> Result<(), LogmapError> {
+        let mut parsed_filters: Vec<(Option<FilterId>, Vec<Column>, Option<String>)> = Vec::new();
+        let mut parsed_ids = HashSet::new();
+        let mut file_comment: Vec<&str> = Vec::new();
+        let mut comment: Vec<&str> = Vec::new();
+        for (line_index, line) in log_filters_lines.iter().enumerate() {
+            if let Some(text) = line.strip_prefix(COMMENT_PREFIX) {
+                comment.push(text.strip_prefix(' ').unwrap_or(text));
+                continue;
+            }
+            if line.trim().is_empty() {
+                // Comment not followed directly by a filter belongs to the whole file
+                file_comment.append(&mut comment);
+                continue;
+            }
+            let bad_filter_line = LogmapError::BadFilterLine {
+                path: None,
+                line_no: first_line_no + line_index,
+            };
+            let (id, line) = match LogFilters::split_filter_id(line) {
+                Some(id_and_line) => id_and_line,
+                None => return Err(bad_filter_line),
+            };
+            if let Some(id) = id {
+                if self.filter_indexes.contains_key(&id) || !parsed_ids.insert(id) {
+                    return Err(bad_filter_line);
+                }
+            }
+            let columns = match self.parse_columns(line, unescape) {
+                Some(columns) => columns,
+                None => return Err(bad_filter_line),
+            };
+            let note = if comment.is_empty() {
+                None
+            } else {
+                Some(comment.join("\n"))
+            };
+            comment.clear();
+            parsed_filters.push((id, columns, note));
+        }
+        file_comment.append(&mut comment);
+        if !file_comment.is_empty() {
+            self.comment = Some(file_comment.join("\n"));
+        }
+
+        // New identifiers must not collide with any of the loaded ones
+        for &(id, _, _) in &parsed_filters {
+            if let Some(id) = id {
+                if id >= self.next_filter_id {
+                    self.next_filter_id = id + 1;
+                }
+            }
+        }
+        for (id, columns, note) in parsed_filters {
+            let id = match id {
+                Some(id) => id,
+                None => self.take_next_filter_id(),
+            };
+            let include_in_hash: Vec<String> = columns
+                .iter()
+                .flat_map(|column| column.alternatives.iter().cloned())
+                .collect();
+            let last_filter_index = self.push_filter(Filter { id, columns, note });
+            for word in include_in_hash {
+                self.update_hash(&word, last_filter_index)
+            }
+        }
 
-#[derive(Default)]
-pub struct LogFilters {
-    /// Each `filters` element stores a vector of individual words variations
-    /// filters (Vec) - collection of all log lines
-    ///    |
-    ///    |- filter (Vec) - collection of word variations within log line
-    ///          |
-    ///          |- word_variations (Vec) - collection of words within word variation
-    ///                   |
-    ///                   |- word1 (String)
-    ///                   |- word2 (String)
-    filters: Vec<Vec<Vec<String>>>,
-    /// Each unique word from `filters` gets its own key
-    /// Each key stores references to lines containing the key
-    words_hash: HashMap<String, Vec<usize>>,
-    /// Maximum allowed new alternatives when analysing any new line
-    pub max_allowed_new_alternatives: usize,
-    /// If `denote_optional` is found within alternatives then column is treated as optional
-    denote_optional: String,
-    /// Should words that contain only numbers be ignored
-    pub ignore_numeric_words: bool,
-    /// Drop first columns before analysing
-    pub ignore_first_columns: usize,
-}
+        Ok(())
+    }
 
-impl LogFilters {
-    pub fn new() -> Self {
-        let filters = Vec::new();
-        let words_hash = HashMap::new();
+    /// Split optional `<id>:` prefix from filter line, `None` if prefix is not a valid identifier
+    fn split_filter_id(line: &str) -> Option<(Option<FilterId>, &str)> {
+        if line.starts_with('[') {
+            return Some((None, line));
+        }
+        let separator = line.find(':')?;
+        let id = line[..separator].parse::<FilterId>().ok()?;
+
+        Some((Some(id), &line[separator + 1..]))
+    }
 
-        LogFilters {
-            filters,
-            words_hash,
-            max_allowed_new_alternatives: 0,
-            // below must never land as word alternative
-            denote_optional: ".".to_string(),
-            ignore_numeric_words: true,
-            ignore_first_columns: 2,
+    /// Parse `[word,word],[word,<denote_optional>]` into columns, `None` if malformed
+    ///
+    /// With `unescape` backslash makes the following character part of the word,
+    /// see `escape_word`.
+    fn parse_columns(&self, line: &str, unescape: bool) -> Option<Vec<Column>> {
+        let mut columns = Vec::new();
+        let mut chars = line.chars();
+        loop {
+            if chars.next() != Some('[') {
+                return None;
+            }
+            let mut column = Column {
+                alternatives: Vec::new(),
+                optional: false,
+            };
+            let mut word = String::new();
+            let mut word_escaped = false;
+            loop {
+                match chars.next()? {
+                    '\\' if unescape => {
+                        word_escaped = true;
+                        word.push(match chars.next()? {
+                            'n' => '\n',
+                            'r' => '\r',
+                            c => c,
+                        });
+                    }
+                    c @ ',' | c @ ']' => {
+                        if !word_escaped && word == self.denote_optional {
+                            column.optional = true;
+                        } else if !word.is_empty() {
+                            column.alternatives.push(word);
+                        }
+                        word = String::new();
+                        word_escaped = false;
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                    '[' => return None,
+                    c => word.push(c),
+                }
+            }
+            if column.alternatives.is_empty() {
+                return None;
+            }
+            columns.push(column);
+
+            // Version 1 files end filter lines with `,`
+            match chars.next() {
+                None => return Some(columns),
+                Some(',') if chars.as_str().is_empty() => return Some(columns),
+                Some(',') => continue,
+                Some(_) => return None,
+            }
         }
     }
 
-    pub fn save(&self, path: &Path) {
-        let mut log_filters_str = String::new();
-        log_filters_str += &self.max_allowed_new_alternatives.to_string();
-        log_filters_str += "\n";
-        log_filters_str += &self.denote_optional;
-        log_filters_str += "\n";
-        log_filters_str += &self.ignore_numeric_words.to_string();
-        log_filters_str += "\n";
-        log_filters_str += &self.ignore_first_columns.to_string();
-        log_filters_str += "\n";
-        log_filters_str += &self.to_string();
+    /// Text form of `filter` as written by `save_to`, without its identifier
+    pub fn filter_to_string(&self, filter: &Filter) -> String {
+        // Vec<Column> -> Vec<String>
+        let word_alternatives: Vec<String> = filter
+            .columns
+            .iter()
+            .map(|column| {
+                let mut words: Vec<String> = column
+                    .alternatives
+                    .iter()
+                    .map(|word| self.escape_word(word))
+                    .collect();
+                if column.optional {
+                    words.push(self.denote_optional.clone());
+                }
+                "[".to_string() + &words.join(",") + "]"
+            })
+            .collect();
+        word_alternatives.join(",")
+    }
 
-        let path_display = path.display();
-        let mut file = match File::create(&path) {
-            Err(why) => panic!("Couldn't create {}: {}", path_display, why.description()),
-            Ok(file) => file,
-        };
-        match file.write_all(log_filters_str.as_bytes()) {
-            Err(why) => panic!("Couldn't write to {}: {}", path_display, why.description()),
-            Ok(_) => println!("Successfully wrote to {}", path_display),
+    /// Anchored regular expression matching lines whose words match `filter` column by column
+    ///
+    /// Syntax is POSIX extended, read the same way by Perl compatible engines (`grep -E`, `grep -P`).
+    /// Lines known only thanks to `max_allowed_new_alternatives` don't match, neither do lines
+    /// with numeric words of other than ASCII digits, e.g. `٣`, which `ignore_numeric_words` skips.
+    /// `None` if the tokenizer does not split on single characters or any class is masked.
+    pub fn filter_to_regex(&self, filter: &Filter) -> Option<String> {
+        if !self.masks.is_empty() {
+            return None;
         }
-    }
+        // Lines hold no line breaks, in patterns they would end a line of `grep -f`
+        let word_delimiters: Vec<char> = self
+            .tokenizer
+            .0
+            .delimiters()?
+            .into_iter()
+            .filter(|&c| c != '\n')
+            .collect();
+        let delimiter = regex_class(&word_delimiters, false, false);
+        let numeric = format!("{}+", regex_class(NUMERIC_SYMBOLS, true, false));
+        // What may follow a word: delimiters and numeric words, or end of line
+        let word_end = if self.ignore_numeric_words {
+            format!("({d}+({n}({d}+|$))*|$)", d = delimiter, n = numeric)
+        } else {
+            format!("({}+|$)", delimiter)
+        };
+        let any_word = if self.ignore_numeric_words {
+            let non_numeric: Vec<char> = word_delimiters
+                .iter()
+                .chain(NUMERIC_SYMBOLS)
+                .cloned()
+                .collect();
+            format!(
+                "{w}*{n}{w}*",
+                w = regex_class(&word_delimiters, false, true),
+                n = regex_class(&non_numeric, true, true)
+            )
+        } else {
+            format!("{}+", regex_class(&word_delimiters, false, true))
+        };
 
-    pub fn to_string(&self) -> String {
-        let mut filters_string: String = String::new();
-        for filter in &self.filters {
-            // Vec<Vec<String>> -> Vec<String>
-            let word_alternatives: Vec<String> = filter
+        let mut regex = format!("^{}*", delimiter);
+        if self.ignore_numeric_words {
+            regex += &format!("({}({}+|$))*", numeric, delimiter);
+        }
+        if self.ignore_first_columns > 0 {
+            regex += &format!(
+                "({}{}){{{}}}",
+                any_word, word_end, self.ignore_first_columns
+            );
+        }
+        for column in &filter.columns {
+            let alternatives: Vec<String> = column
+                .alternatives
                 .iter()
-                .map(|s| "[".to_string() + &s.join(",") + "]")
+                .map(|word| regex_escape(word))
                 .collect();
-            filters_string += &word_alternatives.join(",");
-            filters_string += ",\n";
+            let column_regex = format!("({}){}", alternatives.join("|"), word_end);
+            if column.optional {
+                regex += &format!("({})?", column_regex);
+            } else {
+                regex += &column_regex;
+            }
         }
-        filters_string.pop();
-        filters_string.pop();
+        regex += "$";
 
-        filters_string
+        Some(regex)
     }
 
-    pub fn load(path: &Path) -> Self {
-        let path_display = path.display();
-        let mut file = match File::open(&path) {
-            Err(why) => panic!("Couldn't open {}: {}", path_display, why.description()),
-            Ok(file) => file,
-        };
-        let mut log_filters_str = String::new();
-        file.read_to_string(&mut log_filters_str)
-            .expect("Could not read from file!");
-        let log_filters_lines: Vec<&str> = log_filters_str.split('\n').collect();
+    /// Escape `word` so that `parse_columns` reads it back unchanged
+    ///
+    /// Characters with special meaning are preceded by a backslash, line breaks become `\n` and `\r`.
+    /// Word equal to `denote_optional` gets its first character escaped
+    /// to tell it apart from optional column marker.
+    fn escape_word(&self, word: &str) -> String {
+        let mut escaped = String::with_capacity(word.len());
+        if word == self.denote_optional {
+            escaped.push('\\');
+        }
+        for c in word.chars() {
+            match c {
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                c if LogFilters::is_escaped_char(c) => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
 
-        let mut log_filters = LogFilters::load_parameters(&log_filters_lines);
-        log_filters.from_str_lines(&log_filters_lines);
+    fn is_escaped_char(c: char) -> bool {
+        c == '[' || c == ']' || c == ',' || c == '\\'
+    }
 
-        log_filters
+    fn take_next_filter_id(&mut self) -> FilterId {
+        let id = self.next_filter_id;
+        self.next_filter_id += 1;
+        id
     }
 
-    fn load_parameters(log_filters_lines: &[&str]) -> Self {
-        if log_filters_lines.len() < 5 {
-            panic!(
-                "File is corrupted! At least 5 lines expected, found {}",
-                log_filters_lines.len()
-            )
-        }
+    /// Append `filter` and return its index, words are not added to `words_hash`
+    fn push_filter(&mut self, filter: Filter) -> usize {
+        let filter_index = self.filters.len();
+        self.filter_indexes.insert(filter.id, filter_index);
+        self.filters.push(filter);
+        filter_index
+    }
 
-        let max_allowed_new_alternatives: usize =
-            match log_filters_lines[0].to_string().parse::<usize>() {
-                Err(why) => panic!(
-                    "Couldn't parse 1st line of input to `usize`: {}, {}",
-                    log_filters_lines[0],
-                    why.description()
-                ),
-                Ok(value) => value,
-            };
+    /// Iterate over filters in order they are matched against log lines
+    pub fn filters(&self) -> slice::Iter<'_, Filter> {
+        self.filters.iter()
+    }
 
-        let denote_optional: String;
-        denote_optional = log_filters_lines[1].to_string();
-        if denote_optional.is_empty() {
-            panic!("2nd line of input cannot be empty!");
-        }
+    /// Filter identified by `id`
+    pub fn filter(&self, id: FilterId) -> Option<&Filter> {
+        self.filter_index(id)
+            .map(|filter_index| &self.filters[filter_index])
+    }
 
-        let ignore_numeric_words: bool = match log_filters_lines[2].to_string().parse::<bool>() {
-            Err(why) => panic!(
-                "Couldn't parse 3rd line of input to `bool`: {}, {}",
-                log_filters_lines[2],
-                why.description()
-            ),
-            Ok(value) => value,
-        };
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
 
-        let ignore_first_columns: usize = match log_filters_lines[3].to_string().parse::<usize>() {
-            Err(why) => panic!(
-                "Couldn't parse 4th line of input to `usize`: {}, {}",
-                log_filters_lines[3],
-                why.description()
-            ),
-            Ok(value) => value,
-        };
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
 
-        LogFilters {
-            filters: Vec::new(),
-            words_hash: HashMap::new(),
-            max_allowed_new_alternatives,
-            denote_optional,
-            ignore_numeric_words,
-            ignore_first_columns,
+    /// Identifier of filter stored under `filter_index`
+    pub fn filter_id(&self, filter_index: usize) -> Option<FilterId> {
+        self.filters.get(filter_index).map(|filter| filter.id)
+    }
+
+    /// Attach `note` to filter identified by `id`, `None` removes it
+    ///
+    /// Notes do not take part in matching, they are saved as `#` comment lines
+    /// directly above the filter they belong to.
+    pub fn set_note(&mut self, id: FilterId, note: Option<&str>) -> Result<(), LogmapError> {
+        let filter_index = self.existing_filter_index(id)?;
+        self.filters[filter_index].note = note.map(|note| note.to_string());
+
+        Ok(())
+    }
+
+    /// Comment of the whole filter file, may span multiple lines
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /// Set comment saved at the top of filters, `None` removes it
+    pub fn set_comment(&mut self, comment: Option<&str>) {
+        self.comment = comment.map(|comment| comment.to_string());
+    }
+
+    /// Treat lines matched by `rule` as known, next to lines matching any filter
+    ///
+    /// Rules are checked only by `is_line_known`, they are never extended by learning
+    /// and are not saved together with filters.
+    pub fn add_rule(&mut self, mut rule: Rule) {
+        if let RuleKind::Template(ref mut template) = rule.kind {
+            *template = Some(self.line_split(&self.masks.mask(&rule.pattern)));
         }
+        self.rules.push(rule);
+    }
+
+    pub fn rules(&self) -> slice::Iter<'_, Rule> {
+        self.rules.iter()
+    }
+
+    /// Add rules read from file under `path`, see `load_rules_from`
+    pub fn load_rules(&mut self, path: &Path) -> Result<(), LogmapError> {
+        File::open(path)
+            .map_err(LogmapError::from)
+            .and_then(|file| self.load_rules_from(file))
+            .map_err(|e| e.with_path(path))
     }
 
-    fn from_str_lines(&mut self, log_filters_lines: &[&str]) {
-        for line in log_filters_lines {
-            if !line.contains('[') || !line.contains(']') {
+    /// Add rules read from `reader`, one per line
+    ///
+    /// Each line starts with rule kind, `regex:` or `template:`, followed by the pattern
+    /// given to `Rule::regex` or `Rule::template`. Whitespace after the colon is skipped.
+    /// Empty lines and lines starting with `#` are ignored. No rule is added when any line
+    /// is malformed.
+    pub fn load_rules_from<R: Read>(&mut self, mut reader: R) -> Result<(), LogmapError> {
+        let mut rules_str = String::new();
+        reader.read_to_string(&mut rules_str)?;
+
+        let mut rules = Vec::new();
+        for (i, line) in rules_str.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with(COMMENT_PREFIX) {
                 continue;
             }
-            let mut alternatives = Vec::new();
-            let mut include_in_hash = Vec::new();
-            let alts_iter = line
-                .split(|c| c == '[' || c == ']')
-                .map(|s| s.to_string())
-                .filter(|s| !s.is_empty() && s != ",");
-            for alternative in alts_iter {
-                let words: Vec<String> = alternative
-                    .split(',')
-                    .map(|s| s.to_string())
-                    .filter(|s| !s.is_empty())
-                    .collect();
-                include_in_hash.extend(words.clone());
-                alternatives.push(words);
+            let bad_rule = |reason: String| LogmapError::BadRuleLine {
+                path: None,
+                line: i + 1,
+                reason,
+            };
+            let rule = match line.find(':').map(|colon| line.split_at(colon)) {
+                Some(("regex", pattern)) => Rule::regex(pattern[1..].trim_start()),
+                Some(("template", template)) => Rule::template(template[1..].trim_start()),
+                _ => {
+                    return Err(bad_rule(
+                        "expected `regex:` or `template:` followed by pattern".to_string(),
+                    ))
+                }
+            };
+            match rule {
+                Ok(rule) => rules.push(rule),
+                Err(LogmapError::InvalidRule { reason, .. }) => return Err(bad_rule(reason)),
+                Err(err) => return Err(err),
             }
-            self.filters.push(alternatives);
-            let last_filter_index = self.filters.len() - 1;
-            for word in include_in_hash {
-                if word.is_empty() || word == self.denote_optional {
-                    continue;
+        }
+        for rule in rules {
+            self.add_rule(rule);
+        }
+
+        Ok(())
+    }
+
+    /// Current index of filter identified by `id`
+    pub fn filter_index(&self, id: FilterId) -> Option<usize> {
+        self.filter_indexes.get(&id).cloned()
+    }
+
+    /// Remove filter identified by `id` and return it
+    pub fn remove_filter(&mut self, id: FilterId) -> Result<Filter, LogmapError> {
+        let filter_index = self.existing_filter_index(id)?;
+        let filter = self.filters.remove(filter_index);
+        self.filter_indexes.remove(&id);
+        for index in self.filter_indexes.values_mut() {
+            if *index > filter_index {
+                *index -= 1;
+            }
+        }
+
+        // Indexes of all following filters moved by one
+        for vector_indexes in self.words_hash.values_mut() {
+            vector_indexes.retain(|&index| index != filter_index);
+            for index in vector_indexes.iter_mut() {
+                if *index > filter_index {
+                    *index -= 1;
                 }
-                self.update_hash(&word, last_filter_index)
             }
         }
+        self.words_hash
+            .retain(|_, vector_indexes| !vector_indexes.is_empty());
+
+        Ok(filter)
+    }
+
+    /// Add `word` as alternative of `column` in filter identified by `id`
+    ///
+    /// Returns `false` if `word` already was an alternative of that column. Words `line_to_words`
+    /// never produces, e.g. ones changed by masks or numeric ones while `ignore_numeric_words`
+    /// is set, are rejected as they could never match.
+    pub fn add_alternative(
+        &mut self,
+        id: FilterId,
+        column: usize,
+        word: &str,
+    ) -> Result<bool, LogmapError> {
+        let filter_index = self.existing_column(id, column)?;
+        if self.line_split(&self.masks.mask(word)) != [word]
+            || (self.ignore_numeric_words && is_word_only_numeric(word))
+        {
+            return Err(LogmapError::InvalidWord {
+                word: word.to_string(),
+            });
+        }
+        {
+            let column = &mut self.filters[filter_index].columns[column];
+            if column.contains(word) {
+                return Ok(false);
+            }
+            column.alternatives.push(word.to_string());
+        }
+        self.update_hash(word, filter_index);
+
+        Ok(true)
+    }
+
+    /// Remove `word` from alternatives of `column` in filter identified by `id`
+    ///
+    /// Column left without any word alternative is removed, and so is a filter left without columns.
+    /// Returns `false` if `word` was not an alternative of that column.
+    pub fn remove_alternative(
+        &mut self,
+        id: FilterId,
+        column: usize,
+        word: &str,
+    ) -> Result<bool, LogmapError> {
+        let filter_index = self.existing_column(id, column)?;
+        let word_position = self.filters[filter_index].columns[column]
+            .alternatives
+            .iter()
+            .position(|w| w == word);
+        let word_position = match word_position {
+            Some(word_position) => word_position,
+            None => return Ok(false),
+        };
+        self.filters[filter_index].columns[column]
+            .alternatives
+            .remove(word_position);
+
+        if self.filters[filter_index].columns[column]
+            .alternatives
+            .is_empty()
+        {
+            self.filters[filter_index].columns.remove(column);
+            if self.filters[filter_index].columns.is_empty() {
+                self.remove_filter(id)?;
+                return Ok(true);
+            }
+        }
+        self.remove_from_hash(word, filter_index);
+
+        Ok(true)
+    }
+
+    /// Mark `column` of filter identified by `id` as optional or required
+    pub fn set_column_optional(
+        &mut self,
+        id: FilterId,
+        column: usize,
+        optional: bool,
+    ) -> Result<(), LogmapError> {
+        let filter_index = self.existing_column(id, column)?;
+        self.filters[filter_index].columns[column].optional = optional;
+
+        Ok(())
+    }
+
+    fn existing_filter_index(&self, id: FilterId) -> Result<usize, LogmapError> {
+        self.filter_index(id)
+            .ok_or(LogmapError::UnknownFilter { id })
+    }
+
+    fn existing_column(&self, id: FilterId, column: usize) -> Result<usize, LogmapError> {
+        let filter_index = self.existing_filter_index(id)?;
+        if column >= self.filters[filter_index].columns.len() {
+            return Err(LogmapError::UnknownColumn { id, column });
+        }
+
+        Ok(filter_index)
     }
 
     pub fn print(&self) {
         if !self.filters.is_empty() {
-            for elem in &self.filters {
-                println!("{:?}", elem);
+            for filter in &self.filters {
+                println!("{}: {}", filter.id, self.filter_to_string(filter));
             }
         } else {
             println!("No filters added yet");
@@ -206,75 +2053,440 @@ impl LogFilters {
         }
     }
 
+    /// Find filter best matching `log_line` and describe how line words map onto it
+    pub fn match_line(&self, log_line: &str) -> Option<LineMatch> {
+        let words = self.line_to_words(log_line);
+        let filter_index = self.find_best_matching_filter_index(&words);
+        if filter_index < 0 {
+            return None;
+        }
+        let filter_index = filter_index as usize;
+        let (matched_columns, new_alternatives) = self.map_words_to_filter(&words, filter_index);
+
+        Some(LineMatch {
+            filter_id: self.filters[filter_index].id,
+            filter_index,
+            score: matched_columns.len(),
+            words,
+            matched_columns,
+            new_alternatives,
+        })
+    }
+
+    /// Take read-only snapshot of current filters, see `CompiledFilters`
+    pub fn compile(&self) -> CompiledFilters {
+        CompiledFilters::from(self.clone())
+    }
+
+    /// Check if `log_line` matches any filter or any rule added with `add_rule`
     pub fn is_line_known(&self, log_line: &str) -> bool {
-        let words = self.line_to_words(&log_line);
-        if self.find_best_matching_filter_index(&words) == -1 {
-            return false;
+        let words = self.line_to_words(log_line);
+        if self.find_best_matching_filter_index(&words) != -1 {
+            return true;
         }
 
-        true
+        self.rules
+            .iter()
+            .any(|rule| rule.is_match(log_line, &words))
+    }
+
+    /// Settings of `line_to_words` stored in filter files next to parameters
+    ///
+    /// Filters are only valid for lines split into words the same way they were learned,
+    /// loading applies stored settings with `LogFiltersConfig::with_tokenizer_settings`
+    /// and fails on settings this build can't reproduce.
+    /// Files written before settings were stored were split as by default settings.
+    fn tokenizer_settings(&self) -> Vec<(String, String)> {
+        let mut settings = vec![("kind".to_string(), self.tokenizer.0.kind().to_string())];
+        settings.extend(self.tokenizer.0.settings());
+        settings.push((
+            "numeric_symbols".to_string(),
+            NUMERIC_SYMBOLS.iter().collect(),
+        ));
+        settings.push(("masks".to_string(), self.masks.to_string()));
+        settings
     }
 
     fn line_to_words(&self, log_line: &str) -> Vec<String> {
-        let raw_words = LogFilters::line_split(log_line);
-        let mut words = Vec::new();
+        line_to_words(
+            log_line,
+            &*self.tokenizer.0,
+            &self.masks,
+            self.ignore_numeric_words,
+            self.ignore_first_columns,
+        )
+    }
 
-        let mut i = 0;
-        for word in raw_words {
-            let word = word.to_string();
-            if self.ignore_numeric_words && self.is_word_only_numeric(&word) {
-                continue;
-            }
-            if i < self.ignore_first_columns {
-                i += 1;
-                continue;
-            }
-            words.push(word);
-        }
+    /// Tokenizer splitting log lines into words, see `LogFiltersConfig::with_tokenizer`
+    pub fn tokenizer(&self) -> &dyn Tokenizer {
+        &*self.tokenizer.0
+    }
 
-        words
+    /// Split `log_line` into words with `tokenizer`
+    pub fn line_split(&self, log_line: &str) -> Vec<String> {
+        self.tokenizer.0.tokens(log_line)
     }
 
-    pub fn line_split(log_line: &str) -> Vec<String> {
-        log_line
-            .split(|c| {
-                c == ' '
-                    || c == '/'
-                    || c == ','
-                    || c == '.'
-                    || c == ':'
-                    || c == '"'
-                    || c == '\''
-                    || c == '('
-                    || c == ')'
-                    || c == '{'
-                    || c == '}'
-                    || c == '['
-                    || c == ']'
-            })
-            .map(|s| s.to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
+    /// Classes of words replaced by placeholders before `line_split`
+    pub fn masks(&self) -> &[Mask] {
+        self.masks.masks()
     }
 
-    pub fn learn_line(&mut self, log_line: &str) {
-        let words = self.line_to_words(&log_line);
+    pub fn learn_line(&mut self, log_line: &str) -> LearnOutcome {
+        let words = self.line_to_words(log_line);
+        if words.is_empty() {
+            return LearnOutcome::Skipped;
+        }
 
         let matched_filter_index = self.find_best_matching_filter_index(&words);
         if matched_filter_index >= 0 {
-            self.update_filter(&words, matched_filter_index as usize);
+            let filter_index = matched_filter_index as usize;
+            let filter_before = self.filters[filter_index].columns.clone();
+            self.update_filter(&words, filter_index);
+            self.describe_filter_update(&filter_before, filter_index)
         } else {
             self.add_filter(words);
+            LearnOutcome::NewFilter {
+                id: self.filters[self.filters.len() - 1].id,
+            }
         }
     }
 
-    fn is_word_only_numeric(&self, word: &str) -> bool {
-        let chars_are_numeric: Vec<bool> = word
-            .chars()
-            .map(|c| c == '*' || c == '#' || c.is_numeric())
+    /// Combine filters of `other` with own filters
+    ///
+    /// Each filter of `other` is matched against own filters the same way a log line is
+    /// in `learn_line`, equivalent filters are combined into one holding alternatives of both,
+    /// the rest is added as new filters. Own parameters are kept, `ignore_first_columns`,
+    /// `ignore_numeric_words`, `tokenizer` and `masks` of `other` that differ are reported as
+    /// `ConfigConflict`. Filters of `other` are adjusted to own parameters where words they
+    /// were learned from can be told: numeric alternatives are dropped if only own filters ignore
+    /// them, alternatives own tokenizer would split or own masks would replace are dropped,
+    /// leading columns are dropped
+    /// if `other` ignored fewer of them and both treat numeric words alike.
+    pub fn merge(&mut self, other: &LogFilters) -> Vec<ConfigConflict> {
+        let mut conflicts = Vec::new();
+        if other.ignore_numeric_words != self.ignore_numeric_words {
+            conflicts.push(ConfigConflict {
+                parameter: "ignore_numeric_words",
+                loaded: self.ignore_numeric_words.to_string(),
+                requested: other.ignore_numeric_words.to_string(),
+                overridden: false,
+            });
+        }
+        let mut skipped_columns = 0;
+        if other.ignore_first_columns != self.ignore_first_columns {
+            conflicts.push(ConfigConflict {
+                parameter: "ignore_first_columns",
+                loaded: self.ignore_first_columns.to_string(),
+                requested: other.ignore_first_columns.to_string(),
+                overridden: false,
+            });
+            if other.ignore_first_columns < self.ignore_first_columns
+                && other.ignore_numeric_words == self.ignore_numeric_words
+            {
+                skipped_columns = self.ignore_first_columns - other.ignore_first_columns;
+            }
+        }
+        // Words of other filters this tokenizer would split or these masks replace never match
+        let resplit = other.tokenizer != self.tokenizer || other.masks != self.masks;
+        if other.tokenizer != self.tokenizer {
+            conflicts.push(ConfigConflict {
+                parameter: "tokenizer",
+                loaded: self.tokenizer.to_string(),
+                requested: other.tokenizer.to_string(),
+                overridden: false,
+            });
+        }
+        if other.masks != self.masks {
+            conflicts.push(ConfigConflict {
+                parameter: "masks",
+                loaded: format!("{:?}", self.masks.to_string()),
+                requested: format!("{:?}", other.masks.to_string()),
+                overridden: false,
+            });
+        }
+        let drop_numeric = self.ignore_numeric_words && !other.ignore_numeric_words;
+
+        for filter in &other.filters {
+            let columns: Vec<Column> = filter
+                .columns
+                .iter()
+                .skip(skipped_columns)
+                .map(|column| Column {
+                    alternatives: column
+                        .alternatives
+                        .iter()
+                        .filter(|word| !drop_numeric || !is_word_only_numeric(word))
+                        .filter(|word| {
+                            !resplit || self.line_split(&self.masks.mask(word)) == [word.as_str()]
+                        })
+                        .cloned()
+                        .collect(),
+                    optional: column.optional,
+                })
+                .filter(|column| !column.alternatives.is_empty())
+                .collect();
+            self.merge_columns(columns, &filter.note);
+        }
+
+        conflicts
+    }
+
+    /// Combine `columns` with best matching filter or add them as a new filter
+    ///
+    /// `note` is kept unless the matching filter has its own one.
+    fn merge_columns(&mut self, columns: Vec<Column>, note: &Option<String>) -> LearnOutcome {
+        let words: Vec<String> = columns
+            .iter()
+            .map(|column| column.alternatives[0].clone())
+            .collect();
+        if words.is_empty() {
+            return LearnOutcome::Skipped;
+        }
+
+        let matched_filter_index = self.find_best_matching_filter_index(&words);
+        if matched_filter_index < 0 {
+            let filter_index = self.filters.len();
+            let id = self.take_next_filter_id();
+            self.push_filter(Filter {
+                id,
+                columns,
+                note: note.clone(),
+            });
+            for column in self.filters[filter_index].columns.clone() {
+                for word in &column.alternatives {
+                    self.update_hash(word, filter_index);
+                }
+            }
+            return LearnOutcome::NewFilter { id };
+        }
+
+        let filter_index = matched_filter_index as usize;
+        if self.filters[filter_index].note.is_none() {
+            self.filters[filter_index].note = note.clone();
+        }
+        let filter_before = self.filters[filter_index].columns.clone();
+        self.update_filter(&words, filter_index);
+        // Columns are aligned now, union what first alternatives didn't bring in
+        let (matched_columns, _) = self.map_words_to_filter(&words, filter_index);
+        for (word_index, column_index) in matched_columns {
+            let column = &columns[word_index];
+            for word in &column.alternatives {
+                let alternatives = &mut self.filters[filter_index].columns[column_index];
+                if !alternatives.contains(word) {
+                    alternatives.alternatives.push(word.clone());
+                    self.update_hash(word, filter_index);
+                }
+            }
+            if column.optional {
+                self.filters[filter_index].columns[column_index].optional = true;
+            }
+        }
+        self.describe_filter_update(&filter_before, filter_index)
+    }
+
+    /// List changes turning own filters (old ones) into `new` filters
+    ///
+    /// Filters are paired regardless of their position: first those with equal columns, then
+    /// those whose new version keeps all old alternatives (e.g. learned from more lines),
+    /// preferring the same identifier, at last the remaining ones with the same identifier
+    /// and at least one word in common.
+    /// Removed filters are listed first, followed by added and changed ones in order of `new`.
+    pub fn diff(&self, new: &LogFilters) -> Vec<FilterChange> {
+        let mut old_matched = vec![false; self.filters.len()];
+        let mut new_matches: Vec<Option<usize>> = vec![None; new.filters.len()];
+
+        let mut old_by_columns: HashMap<&[Column], Vec<usize>> = HashMap::new();
+        for (old_index, filter) in self.filters.iter().enumerate().rev() {
+            old_by_columns
+                .entry(&filter.columns)
+                .or_default()
+                .push(old_index);
+        }
+        for (new_index, filter) in new.filters.iter().enumerate() {
+            if let Some(old_index) = old_by_columns
+                .get_mut(&filter.columns[..])
+                .and_then(|old_indexes| old_indexes.pop())
+            {
+                old_matched[old_index] = true;
+                new_matches[new_index] = Some(old_index);
+            }
+        }
+
+        let old_by_id: HashMap<FilterId, usize> = self
+            .filters
+            .iter()
+            .enumerate()
+            .map(|(old_index, filter)| (filter.id, old_index))
             .collect();
+        // Number of old alternatives missing from `filter`
+        let count_removed = |old_index: usize, filter: &Filter| {
+            LogFilters::compare_columns(&self.filters[old_index].columns, &filter.columns)
+                .1
+                .len()
+        };
+        let is_extended = |old_index: usize, filter: &Filter| count_removed(old_index, filter) == 0;
+        let is_related = |old_index: usize, filter: &Filter| {
+            let old_alternatives: usize = self.filters[old_index]
+                .columns
+                .iter()
+                .map(|column| column.alternatives.len())
+                .sum();
+            count_removed(old_index, filter) < old_alternatives
+        };
+        // Identifiers are trusted only once content can't pair filters,
+        // filters learned from scratch get identifiers in order of lines
+        for pass in 0..3 {
+            for (new_index, filter) in new.filters.iter().enumerate() {
+                if new_matches[new_index].is_some() {
+                    continue;
+                }
+                let same_id = old_by_id
+                    .get(&filter.id)
+                    .cloned()
+                    .filter(|&old_index| !old_matched[old_index]);
+                let old_match = match pass {
+                    0 => same_id.filter(|&old_index| is_extended(old_index, filter)),
+                    1 => (0..self.filters.len()).find(|&old_index| {
+                        !old_matched[old_index] && is_extended(old_index, filter)
+                    }),
+                    _ => same_id.filter(|&old_index| is_related(old_index, filter)),
+                };
+                if let Some(old_index) = old_match {
+                    old_matched[old_index] = true;
+                    new_matches[new_index] = Some(old_index);
+                }
+            }
+        }
+
+        let mut changes: Vec<FilterChange> = self
+            .filters
+            .iter()
+            .zip(old_matched)
+            .filter(|&(_, matched)| !matched)
+            .map(|(filter, _)| FilterChange::Removed(filter.clone()))
+            .collect();
+        for (filter, new_match) in new.filters.iter().zip(new_matches) {
+            let old = match new_match {
+                Some(old_index) => &self.filters[old_index],
+                None => {
+                    changes.push(FilterChange::Added(filter.clone()));
+                    continue;
+                }
+            };
+            if old.columns == filter.columns {
+                continue;
+            }
+            let (added_alternatives, removed_alternatives, new_optional_columns) =
+                LogFilters::compare_columns(&old.columns, &filter.columns);
+            changes.push(FilterChange::Changed {
+                old: old.clone(),
+                new: filter.clone(),
+                added_alternatives,
+                removed_alternatives,
+                new_optional_columns,
+            });
+        }
+
+        changes
+    }
+
+    /// Pair columns sharing a word, in order, and list what differs between paired columns
+    ///
+    /// Returns alternatives added to `new` columns, alternatives missing from `old` columns
+    /// and `new` columns which became optional, see `FilterChange::Changed`.
+    fn compare_columns(old: &[Column], new: &[Column]) -> (ColumnWords, ColumnWords, Vec<usize>) {
+        let mut pairs: Vec<Option<usize>> = vec![None; new.len()];
+        let mut removed_alternatives = Vec::new();
+        let mut next_new_index = 0;
+        for (old_index, old_column) in old.iter().enumerate() {
+            let paired = (next_new_index..new.len()).find(|&new_index| {
+                old_column
+                    .alternatives
+                    .iter()
+                    .any(|word| new[new_index].contains(word))
+            });
+            match paired {
+                Some(new_index) => {
+                    pairs[new_index] = Some(old_index);
+                    next_new_index = new_index + 1;
+                    for word in &old_column.alternatives {
+                        if !new[new_index].contains(word) {
+                            removed_alternatives.push((old_index, word.clone()));
+                        }
+                    }
+                }
+                None => {
+                    for word in &old_column.alternatives {
+                        removed_alternatives.push((old_index, word.clone()));
+                    }
+                }
+            }
+        }
+
+        let mut added_alternatives = Vec::new();
+        let mut new_optional_columns = Vec::new();
+        for (new_index, (new_column, paired)) in new.iter().zip(pairs).enumerate() {
+            let old_column = paired.map(|old_index| &old[old_index]);
+            for word in &new_column.alternatives {
+                if !old_column.is_some_and(|old_column| old_column.contains(word)) {
+                    added_alternatives.push((new_index, word.clone()));
+                }
+            }
+            if new_column.optional && !old_column.is_some_and(|old_column| old_column.optional) {
+                new_optional_columns.push(new_index);
+            }
+        }
+
+        (
+            added_alternatives,
+            removed_alternatives,
+            new_optional_columns,
+        )
+    }
+
+    /// Compare filter with its state before `update_filter`
+    ///
+    /// `update_filter` only inserts columns, appends alternatives and marks columns optional,
+    /// so alternatives of every column from `filter_before` are a prefix of some column of the updated filter.
+    fn describe_filter_update(
+        &self,
+        filter_before: &[Column],
+        filter_index: usize,
+    ) -> LearnOutcome {
+        let filter = &self.filters[filter_index];
+        let mut added_alternatives = Vec::new();
+        let mut new_optional_columns = Vec::new();
+
+        let mut old_columns = filter_before.iter().peekable();
+        for (column_index, column) in filter.columns.iter().enumerate() {
+            let old_column = match old_columns.peek() {
+                Some(old) if column.alternatives.starts_with(&old.alternatives) => Some(*old),
+                _ => None,
+            };
+            if old_column.is_some() {
+                old_columns.next();
+            }
+            let (old_length, was_optional) = old_column
+                .map(|old| (old.alternatives.len(), old.optional))
+                .unwrap_or((0, false));
+            for word in column.alternatives.iter().skip(old_length) {
+                added_alternatives.push((column_index, word.clone()));
+            }
+            if column.optional && !was_optional {
+                new_optional_columns.push(column_index);
+            }
+        }
 
-        !chars_are_numeric.contains(&false)
+        if added_alternatives.is_empty() && new_optional_columns.is_empty() {
+            LearnOutcome::Unchanged { id: filter.id }
+        } else {
+            LearnOutcome::Extended {
+                id: filter.id,
+                added_alternatives,
+                new_optional_columns,
+            }
+        }
     }
 
     fn find_best_matching_filter_index(&self, words: &[String]) -> isize {
@@ -301,7 +2513,8 @@ impl LogFilters {
             if max_consequent_matches_indexes.len() > 1 {
                 let mut matching_filters: String = String::new();
                 for filter_index in max_consequent_matches_indexes {
-                    matching_filters += &format!("{:?}, ", self.filters[filter_index]);
+                    matching_filters += &self.filter_to_string(&self.filters[filter_index]);
+                    matching_filters += ", ";
                 }
                 eprintln!(
                     "More than one matching filter found. Words: {:?}; Filters: {}",
@@ -329,19 +2542,18 @@ impl LogFilters {
             if prev_index != filter_index as isize {
                 matches = 1;
                 prev_index = filter_index as isize;
-                optional_alternatives = 0;
-                for word_alternatives in &self.filters[filter_index] {
-                    if word_alternatives.contains(&self.denote_optional) {
-                        optional_alternatives += 1;
-                    }
-                }
+                optional_alternatives = self.filters[filter_index]
+                    .columns
+                    .iter()
+                    .filter(|column| column.optional)
+                    .count();
             } else {
                 matches += 1;
             }
 
             if matches as isize >= words.len() as isize - self.max_allowed_new_alternatives as isize
                 && matches as isize
-                    >= self.filters[filter_index].len() as isize
+                    >= self.filters[filter_index].columns.len() as isize
                         - self.max_allowed_new_alternatives as isize
                         - optional_alternatives as isize
             {
@@ -357,7 +2569,7 @@ impl LogFilters {
     fn get_sorted_filter_indexes_containing_words(&self, words: &[String]) -> Vec<usize> {
         let mut filters_with_words: Vec<usize> = Vec::new();
         for word in words {
-            if self.words_hash.get(word).is_some() {
+            if self.words_hash.contains_key(word) {
                 let vector_indexes = &self.words_hash[word];
                 filters_with_words.extend(vector_indexes);
             }
@@ -371,18 +2583,37 @@ impl LogFilters {
         if self.filters.len() <= filter_index || words.is_empty() {
             return 0;
         }
-        let mut consequent_matches: usize = 0;
-        let mut max_consequent_matches: usize = 0;
-        let mut new_alternatives: usize = 0;
 
         let mut extra_allowed_new_alternatives: usize = 0;
-        let filter_length = self.filters[filter_index].len();
+        let filter_length = self.filters[filter_index].columns.len();
         if filter_length < words.len() {
             extra_allowed_new_alternatives = words.len() - filter_length;
         }
 
+        let (matched_columns, new_alternatives) = self.map_words_to_filter(words, filter_index);
+        if new_alternatives.len()
+            > self.max_allowed_new_alternatives + extra_allowed_new_alternatives
+        {
+            return 0;
+        }
+
+        matched_columns.len()
+    }
+
+    /// Walk `words` in order and find filter column matching each of them
+    ///
+    /// Returns `(word index, column index)` pairs of matching words
+    /// and indexes of words without matching column.
+    fn map_words_to_filter(
+        &self,
+        words: &[String],
+        filter_index: usize,
+    ) -> (Vec<(usize, usize)>, Vec<usize>) {
+        let mut matched_columns = Vec::new();
+        let mut new_alternatives = Vec::new();
+
         let mut last_matching_index: isize = -1;
-        for word in words {
+        for (word_index, word) in words.iter().enumerate() {
             let mathing_index = self.get_word_index_in_filter(
                 word,
                 filter_index,
@@ -390,21 +2621,13 @@ impl LogFilters {
             );
             if mathing_index >= 0 && mathing_index > last_matching_index {
                 last_matching_index = mathing_index;
-                consequent_matches += 1;
-                if consequent_matches > max_consequent_matches {
-                    max_consequent_matches = consequent_matches;
-                }
+                matched_columns.push((word_index, mathing_index as usize));
             } else {
-                new_alternatives += 1;
-                if new_alternatives
-                    > self.max_allowed_new_alternatives + extra_allowed_new_alternatives
-                {
-                    return 0;
-                }
+                new_alternatives.push(word_index);
             }
         }
 
-        max_consequent_matches
+        (matched_columns, new_alternatives)
     }
 
     fn get_word_index_in_filter(
@@ -416,7 +2639,7 @@ impl LogFilters {
         if word.is_empty() {
             return -1;
         }
-        if self.words_hash.get(word).is_none() {
+        if !self.words_hash.contains_key(word) {
             return -1;
         }
         if !&self.words_hash[word].contains(&filter_index) {
@@ -426,7 +2649,7 @@ impl LogFilters {
         if filter.is_none() {
             return -1;
         }
-        let filter = filter.unwrap();
+        let filter = &filter.unwrap().columns;
         if filter.is_empty() || filter.len() - 1 < start_from_word {
             return -1;
         }
@@ -434,7 +2657,7 @@ impl LogFilters {
         for (word_alternative_index, word_alternative) in
             filter.iter().enumerate().skip(start_from_word)
         {
-            if word_alternative.contains(&word.to_owned()) {
+            if word_alternative.contains(word) {
                 return word_alternative_index as isize;
             }
         }
@@ -444,10 +2667,10 @@ impl LogFilters {
 
     // TODO: decompose below into smaller and simpler methods
     fn update_filter(&mut self, words: &[String], filter_index: usize) {
-        let mut indexes = self.normalise_lengths_before_first_match(&words, filter_index, 0, 0);
+        let mut indexes = self.normalise_lengths_before_first_match(words, filter_index, 0, 0);
         while indexes.0 >= 0 && indexes.1 >= 0 && words.len() > indexes.0 as usize {
             let new_indexes = self.normalise_lengths_before_first_match(
-                &words,
+                words,
                 filter_index,
                 indexes.0 as usize,
                 indexes.1 as usize,
@@ -461,7 +2684,7 @@ impl LogFilters {
                 if indexes.0 == words.len() as isize - 1 {
                     break;
                 }
-                if indexes.1 == self.filters[filter_index].len() as isize - 1 {
+                if indexes.1 == self.filters[filter_index].columns.len() as isize - 1 {
                     break;
                 }
                 indexes.0 += 1;
@@ -469,24 +2692,24 @@ impl LogFilters {
             }
         }
         if indexes.0 >= 0 && indexes.1 >= 0 {
-            let filter_length = { self.filters[filter_index].len() };
+            let filter_length = { self.filters[filter_index].columns.len() };
             if words.len() > filter_length && indexes.1 == filter_length as isize - 1 {
                 for extra_word in 0..words.len() - filter_length {
                     {
-                        let mut filter = &mut self.filters[filter_index];
-                        filter.push(vec![
-                            words[filter_length + extra_word].clone(),
-                            self.denote_optional.clone(),
-                        ]);
+                        let filter = &mut self.filters[filter_index].columns;
+                        filter.push(Column {
+                            alternatives: vec![words[filter_length + extra_word].clone()],
+                            optional: true,
+                        });
                     }
                     self.update_hash(&words[filter_length + extra_word].clone(), filter_index);
                 }
             } else if indexes.0 < words.len() as isize {
                 let mut reversed_words = words.to_owned();
                 reversed_words.reverse();
-                self.filters[filter_index].reverse();
+                self.filters[filter_index].columns.reverse();
                 self.normalise_lengths_before_first_match(&reversed_words, filter_index, 0, 0);
-                self.filters[filter_index].reverse();
+                self.filters[filter_index].columns.reverse();
             }
         }
     }
@@ -501,7 +2724,7 @@ impl LogFilters {
     ) -> (isize, isize) {
         // returns first index after normalised filter slice
         let (first_word, first_filter) = self.get_indexes_of_earliest_matching_word(
-            &words,
+            words,
             filter_index,
             word_start_index,
             filter_start_index,
@@ -514,24 +2737,27 @@ impl LogFilters {
             let mut front_words = Vec::new();
             let mut updates: isize = 0;
             for word in &words[word_start_index..first_word as usize] {
-                front_words.push(vec![word.clone(), self.denote_optional.clone()]);
+                front_words.push(Column {
+                    alternatives: vec![word.clone()],
+                    optional: true,
+                });
                 updates += 1;
             }
             // TODO: check if below can be done in more elegant way
             {
                 let first_filter = first_filter as usize;
-                let filter = &mut self.filters[filter_index];
+                let filter = &mut self.filters[filter_index].columns;
                 filter.splice(first_filter..first_filter, front_words);
             }
             for word in &words[word_start_index..first_word as usize] {
-                self.update_hash(&word, filter_index);
+                self.update_hash(word, filter_index);
             }
 
             (first_word, first_filter + updates)
         } else {
             {
                 // Mark first filter columns as optional alternatives
-                let filter = &mut self.filters[filter_index];
+                let filter = &mut self.filters[filter_index].columns;
                 for word_alternatives in filter
                     .iter_mut()
                     .take(
@@ -540,20 +2766,22 @@ impl LogFilters {
                     )
                     .skip(filter_start_index)
                 {
-                    if !word_alternatives.contains(&self.denote_optional) {
-                        word_alternatives.push(self.denote_optional.clone());
-                    }
+                    word_alternatives.optional = true;
                 }
                 // Add new alternatives if filter length before first match was longer than words index
-                let mut word_index: usize = word_start_index;
-                for word_alternatives in filter.iter_mut().take(first_filter as usize).skip(
-                    (filter_start_index as isize + first_filter - first_word - filters_offset)
-                        as usize,
-                ) {
-                    if !word_alternatives.contains(&words[word_index]) {
-                        word_alternatives.push(words[word_index].clone());
+                for (word_index, word_alternatives) in filter
+                    .iter_mut()
+                    .take(first_filter as usize)
+                    .skip(
+                        (filter_start_index as isize + first_filter - first_word - filters_offset)
+                            as usize,
+                    )
+                    .enumerate()
+                {
+                    let word = &words[word_start_index + word_index];
+                    if !word_alternatives.contains(word) {
+                        word_alternatives.alternatives.push(word.clone());
                     }
-                    word_index += 1;
                 }
             }
             for word in words
@@ -561,7 +2789,7 @@ impl LogFilters {
                 .take(first_word as usize)
                 .skip(word_start_index)
             {
-                self.update_hash(&word, filter_index);
+                self.update_hash(word, filter_index);
             }
 
             (first_word, first_filter)
@@ -580,7 +2808,7 @@ impl LogFilters {
         {
             return (-1, -1);
         }
-        if self.filters[filter_index].len() as isize - 1 < filter_start_index as isize {
+        if self.filters[filter_index].columns.len() as isize - 1 < filter_start_index as isize {
             return (-1, -1);
         }
 
@@ -589,7 +2817,7 @@ impl LogFilters {
         let mut first_matching_filter: isize = -1;
         for (word_index, word) in words.iter().enumerate().skip(word_start_index) {
             let matching_filter_index = self.get_word_index_in_filter(
-                &word,
+                word,
                 filter_index,
                 (word_start_index as isize + filters_offset) as usize,
             );
@@ -610,13 +2838,18 @@ impl LogFilters {
 
         for word in words {
             if !word.is_empty() {
-                new_filter.push(vec![word]);
+                new_filter.push(Column::new(&word));
             }
         }
         if !new_filter.is_empty() {
-            self.filters.push(new_filter.clone());
+            let id = self.take_next_filter_id();
+            self.push_filter(Filter {
+                id,
+                columns: new_filter.clone(),
+                note: None,
+            });
             for word_alternatives in new_filter {
-                self.update_hash(&word_alternatives[0], expected_index);
+                self.update_hash(&word_alternatives.alternatives[0], expected_index);
             }
         }
     }
@@ -634,6 +2867,23 @@ impl LogFilters {
         }
     }
 
+    /// Drop reference to `filter_index` from `words_hash` unless filter still contains `word`
+    fn remove_from_hash(&mut self, word: &str, filter_index: usize) {
+        if self.is_word_in_filter(word, filter_index) {
+            return;
+        }
+        let remove_key = match self.words_hash.get_mut(word) {
+            Some(vector_indexes) => {
+                vector_indexes.retain(|&index| index != filter_index);
+                vector_indexes.is_empty()
+            }
+            None => false,
+        };
+        if remove_key {
+            self.words_hash.remove(word);
+        }
+    }
+
     fn is_word_in_filter(&self, word: &str, filter_index: usize) -> bool {
         let filter = self.filters.get(filter_index);
         if filter.is_none() {
@@ -641,8 +2891,8 @@ impl LogFilters {
         }
 
         let filter = filter.unwrap();
-        for word_alternatives in filter {
-            if word_alternatives.contains(&word.to_owned()) {
+        for word_alternatives in &filter.columns {
+            if word_alternatives.contains(word) {
                 return true;
             }
         }
@@ -651,45 +2901,817 @@ impl LogFilters {
     }
 }
 
-#[cfg(feature = "tst_utils")]
+impl fmt::Display for LogFilters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let filters: Vec<String> = self
+            .filters
+            .iter()
+            .map(|filter| self.filter_to_string(filter))
+            .collect();
+
+        write!(f, "{}", filters.join(",\n"))
+    }
+}
+
+/// Read-only form of `LogFilters` built only to check log lines
+///
+/// Learning state such as the word index keyed by `String`s, the next filter identifier and the
+/// file comment is dropped. Words of filters are interned instead: every column holds sorted
+/// identifiers of its words and each identifier lists filters holding the word, so lines are
+/// matched comparing integers. Regular expressions of rules are combined into one `RegexSet`.
+/// Lines are matched as by `LogFilters` except that ambiguous matches are not printed.
+/// It is `Send + Sync` and never changes once built, so it can be shared behind an `Arc`
+/// by many readers while the learning `LogFilters` keeps running.
+/// Readers pick up new filters by swapping to a newer snapshot.
+#[derive(Clone, Debug)]
+pub struct CompiledFilters {
+    /// Filters as they were compiled, see `filters`
+    filters: Vec<Filter>,
+    /// Index in `filters` of each filter identifier
+    filter_indexes: HashMap<FilterId, usize>,
+    /// Identifier of every word found in filters
+    word_ids: HashMap<String, usize>,
+    /// Sorted indexes of filters holding each word, by word identifier
+    word_filters: Vec<Vec<usize>>,
+    /// Sorted identifiers of words of each column, by filter index
+    columns: Vec<Vec<Vec<usize>>>,
+    /// Number of columns that are not optional, by filter index
+    required_columns: Vec<usize>,
+    rules: Vec<Rule>,
+    rule_matcher: RuleMatcher,
+    max_allowed_new_alternatives: usize,
+    denote_optional: String,
+    ignore_numeric_words: bool,
+    ignore_first_columns: usize,
+    tokenizer: SharedTokenizer,
+    masks: Masker,
+}
+
+impl CompiledFilters {
+    /// See `LogFilters::match_line`
+    pub fn match_line(&self, log_line: &str) -> Option<LineMatch> {
+        let words = self.line_to_words(log_line);
+        let word_ids = self.word_ids(&words);
+        let filter_index = self.matching_filter_index(&word_ids)?;
+        let (matched_columns, new_alternatives) = self.map_words_to_filter(&word_ids, filter_index);
+
+        Some(LineMatch {
+            filter_id: self.filters[filter_index].id,
+            filter_index,
+            score: matched_columns.len(),
+            words,
+            matched_columns,
+            new_alternatives,
+        })
+    }
+
+    /// See `LogFilters::is_line_known`
+    pub fn is_line_known(&self, log_line: &str) -> bool {
+        let words = self.line_to_words(log_line);
+        self.matching_filter_index(&self.word_ids(&words)).is_some()
+            || self.rule_matcher.is_match(log_line, &words)
+    }
+
+    pub fn rules(&self) -> slice::Iter<'_, Rule> {
+        self.rules.iter()
+    }
+
+    pub fn filters(&self) -> slice::Iter<'_, Filter> {
+        self.filters.iter()
+    }
+
+    pub fn filter(&self, id: FilterId) -> Option<&Filter> {
+        self.filter_indexes
+            .get(&id)
+            .map(|&filter_index| &self.filters[filter_index])
+    }
+
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Configuration the snapshot was built with
+    pub fn config(&self) -> LogFiltersConfig {
+        let mut config = LogFiltersConfig::new()
+            .with_max_allowed_new_alternatives(self.max_allowed_new_alternatives)
+            .with_denote_optional(&self.denote_optional)
+            .with_ignore_numeric_words(self.ignore_numeric_words)
+            .with_ignore_first_columns(self.ignore_first_columns);
+        config.tokenizer = Some(self.tokenizer.clone());
+        config.masks = Some(self.masks.clone());
+        config
+    }
+
+    fn line_to_words(&self, log_line: &str) -> Vec<String> {
+        line_to_words(
+            log_line,
+            &*self.tokenizer.0,
+            &self.masks,
+            self.ignore_numeric_words,
+            self.ignore_first_columns,
+        )
+    }
+
+    /// Identifier of each of `words`, `None` for words found in no filter
+    fn word_ids(&self, words: &[String]) -> Vec<Option<usize>> {
+        words
+            .iter()
+            .map(|word| self.word_ids.get(word).cloned())
+            .collect()
+    }
+
+    /// Index of the filter `LogFilters::find_best_matching_filter_index` picks for same words
+    fn matching_filter_index(&self, word_ids: &[Option<usize>]) -> Option<usize> {
+        let mut best_matching_filter_index = None;
+        let mut max_consequent_matches = 0;
+        for filter_index in self.filter_indexes_with_min_req_matches(word_ids) {
+            let consequent_matches = self.count_consequent_matches(word_ids, filter_index);
+            if consequent_matches > max_consequent_matches {
+                max_consequent_matches = consequent_matches;
+                best_matching_filter_index = Some(filter_index);
+            }
+        }
+        if max_consequent_matches as isize
+            >= word_ids.len() as isize - self.max_allowed_new_alternatives as isize
+        {
+            return best_matching_filter_index;
+        }
+
+        None
+    }
+
+    /// Sorted indexes of filters holding enough of `word_ids` to be matched
+    fn filter_indexes_with_min_req_matches(&self, word_ids: &[Option<usize>]) -> Vec<usize> {
+        let mut filters_with_words: Vec<usize> = word_ids
+            .iter()
+            .filter_map(|&word_id| word_id)
+            .flat_map(|word_id| self.word_filters[word_id].iter().cloned())
+            .collect();
+        filters_with_words.sort();
+
+        let allowed = self.max_allowed_new_alternatives as isize;
+        let mut filter_indexes = Vec::new();
+        let mut i = 0;
+        while i < filters_with_words.len() {
+            let filter_index = filters_with_words[i];
+            let matches = filters_with_words[i..]
+                .iter()
+                .take_while(|&&index| index == filter_index)
+                .count();
+            i += matches;
+            if matches as isize >= word_ids.len() as isize - allowed
+                && matches as isize >= self.required_columns[filter_index] as isize - allowed
+            {
+                filter_indexes.push(filter_index);
+            }
+        }
+
+        filter_indexes
+    }
+
+    fn count_consequent_matches(&self, word_ids: &[Option<usize>], filter_index: usize) -> usize {
+        let extra_allowed_new_alternatives = word_ids
+            .len()
+            .saturating_sub(self.columns[filter_index].len());
+        let (matched_columns, new_alternatives) = self.map_words_to_filter(word_ids, filter_index);
+        if new_alternatives.len()
+            > self.max_allowed_new_alternatives + extra_allowed_new_alternatives
+        {
+            return 0;
+        }
+
+        matched_columns.len()
+    }
+
+    /// See `LogFilters::map_words_to_filter`
+    fn map_words_to_filter(
+        &self,
+        word_ids: &[Option<usize>],
+        filter_index: usize,
+    ) -> (Vec<(usize, usize)>, Vec<usize>) {
+        let columns = &self.columns[filter_index];
+        let mut matched_columns = Vec::new();
+        let mut new_alternatives = Vec::new();
+
+        let mut next_column = 0;
+        for (word_index, word_id) in word_ids.iter().enumerate() {
+            let column_index = word_id.and_then(|word_id| {
+                columns[next_column..]
+                    .iter()
+                    .position(|column| column.binary_search(&word_id).is_ok())
+                    .map(|offset| next_column + offset)
+            });
+            match column_index {
+                Some(column_index) => {
+                    matched_columns.push((word_index, column_index));
+                    next_column = column_index + 1;
+                }
+                None => new_alternatives.push(word_index),
+            }
+        }
+
+        (matched_columns, new_alternatives)
+    }
+}
+
+/// Rules of `CompiledFilters` checked at once
+#[derive(Clone, Debug)]
+struct RuleMatcher {
+    /// Expressions of all regular expression rules
+    regexes: RegexSet,
+    /// Words of all template rules
+    templates: Vec<Vec<String>>,
+}
+
+impl RuleMatcher {
+    fn new(rules: &[Rule]) -> Self {
+        let mut patterns = Vec::new();
+        let mut templates = Vec::new();
+        for rule in rules {
+            match rule.kind {
+                RuleKind::Regex(ref regex) => patterns.push(regex.as_str()),
+                RuleKind::Template(ref template) => templates.extend(template.clone()),
+            }
+        }
+
+        RuleMatcher {
+            regexes: RegexSet::new(patterns).expect("Rule expressions were already compiled"),
+            templates,
+        }
+    }
+
+    /// Check rules against `log_line` split into `words`
+    fn is_match(&self, log_line: &str, words: &[String]) -> bool {
+        self.regexes.is_match(log_line)
+            || self
+                .templates
+                .iter()
+                .any(|template| Rule::template_matches(template, words))
+    }
+}
+
+/// `LogFilters` as read by `serde`, before words are indexed
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SerializedLogFilters {
+    filters: Vec<Filter>,
+    #[serde(default)]
+    comment: Option<String>,
+    next_filter_id: FilterId,
+    max_allowed_new_alternatives: usize,
+    denote_optional: String,
+    ignore_numeric_words: bool,
+    ignore_first_columns: usize,
+    #[serde(default)]
+    tokenizer: SharedTokenizer,
+    #[serde(default)]
+    masks: Masker,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerializedLogFilters> for LogFilters {
+    type Error = LogmapError;
+
+    fn try_from(serialized: SerializedLogFilters) -> Result<Self, Self::Error> {
+        let mut config = LogFiltersConfig::new()
+            .with_max_allowed_new_alternatives(serialized.max_allowed_new_alternatives)
+            .with_denote_optional(&serialized.denote_optional)
+            .with_ignore_numeric_words(serialized.ignore_numeric_words)
+            .with_ignore_first_columns(serialized.ignore_first_columns);
+        config.tokenizer = Some(serialized.tokenizer);
+        config.masks = Some(serialized.masks);
+        let mut log_filters = config.build()?;
+        log_filters.next_filter_id = serialized.next_filter_id;
+        log_filters.comment = serialized.comment;
+
+        let mut ids = HashSet::new();
+        for filter in serialized.filters {
+            let invalid_filter = |reason| LogmapError::InvalidFilter {
+                id: filter.id,
+                reason,
+            };
+            if !ids.insert(filter.id) {
+                return Err(invalid_filter("identifier is not unique"));
+            }
+            if filter.columns.is_empty() {
+                return Err(invalid_filter("filter has no columns"));
+            }
+            for column in &filter.columns {
+                if column.alternatives.is_empty() {
+                    return Err(invalid_filter("column has no word alternatives"));
+                }
+                if column
+                    .alternatives
+                    .iter()
+                    .any(|word| log_filters.line_split(word) != [word.as_str()])
+                {
+                    return Err(invalid_filter("word alternative is not a single word"));
+                }
+            }
+            if filter.id >= log_filters.next_filter_id {
+                log_filters.next_filter_id = filter.id + 1;
+            }
+
+            let filter_index = log_filters.filters.len();
+            let words: Vec<String> = filter
+                .columns
+                .iter()
+                .flat_map(|column| column.alternatives.iter().cloned())
+                .collect();
+            log_filters.push_filter(filter);
+            for word in words {
+                log_filters.update_hash(&word, filter_index);
+            }
+        }
+
+        Ok(log_filters)
+    }
+}
+
+/// Escape `value` of a text file parameter so it fits one line without leading or trailing spaces
+/// Words of `log_line` compared with filter columns, see `LogFilters::line_to_words`
+fn line_to_words(
+    log_line: &str,
+    tokenizer: &dyn Tokenizer,
+    masks: &Masker,
+    ignore_numeric_words: bool,
+    ignore_first_columns: usize,
+) -> Vec<String> {
+    let raw_words = tokenizer.tokens(&masks.mask(log_line));
+    let mut words = Vec::new();
+
+    let mut i = 0;
+    for word in raw_words {
+        if ignore_numeric_words && is_word_only_numeric(&word) {
+            continue;
+        }
+        if i < ignore_first_columns {
+            i += 1;
+            continue;
+        }
+        words.push(word);
+    }
+
+    words
+}
+
+fn is_word_only_numeric(word: &str) -> bool {
+    let chars_are_numeric: Vec<bool> = word
+        .chars()
+        .map(|c| NUMERIC_SYMBOLS.contains(&c) || c.is_numeric())
+        .collect();
+
+    !chars_are_numeric.contains(&false)
+}
+
+fn escape_parameter(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ' ' => escaped.push_str("\\s"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reverse of `escape_parameter`, `None` when `value` has unknown escape sequence
+fn unescape_parameter(value: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            's' => ' ',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+/// Escape characters with special meaning in POSIX extended regular expressions
+///
+/// POSIX defines a backslash only before these characters, `]` and `}` are literal on their own.
+fn regex_escape(word: &str) -> String {
+    let mut escaped = String::with_capacity(word.len());
+    for c in word.chars() {
+        if "\\.+*?()|[{^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Bracket expression matching any of `chars` (and ASCII digits if `digits`),
+/// or any other character if `negated`
+///
+/// Backslash is literal in POSIX bracket expressions, so special characters are placed
+/// where they can't be misread instead: `]` first, `[` not followed by `.`, `=` or `:`,
+/// `^` not first and `-` last.
+fn regex_class(chars: &[char], digits: bool, negated: bool) -> String {
+    let mut class = String::from(if negated { "[^" } else { "[" });
+    if chars.contains(&']') {
+        class.push(']');
+    }
+    if digits {
+        class += "0-9";
+    }
+    for &c in chars.iter().filter(|c| !"[]^-".contains(**c)) {
+        // Doubled backslash is one backslash for Perl compatible engines, twice the same for POSIX
+        if c == '\\' {
+            class.push('\\');
+        }
+        class.push(c);
+    }
+    for &c in &['[', '^', '-'] {
+        if chars.contains(&c) {
+            class.push(c);
+        }
+    }
+    if !negated {
+        // `^` left first would read as negation
+        match class.as_str() {
+            "[^" => return "\\^".to_string(),
+            "[^-" => return "[-^]".to_string(),
+            _ => {}
+        }
+    }
+    class.push(']');
+    class
+}
+
+/// `#` comment lines holding `text`, one per line of `text`
+fn comment_lines(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            if line.is_empty() {
+                format!("{}\n", COMMENT_PREFIX)
+            } else {
+                format!("{} {}\n", COMMENT_PREFIX, line)
+            }
+        })
+        .collect()
+}
+
+/// Path of backup kept by `LogFilters::save_with_backup`
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = OsString::from(path.as_os_str());
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Replace `path` with content produced by `write`
+///
+/// Content goes to a new file in the same directory first, which is synced to disk
+/// and renamed over `path` only once `write` succeeded. Temporary file is removed on failure.
+fn write_atomically<F>(path: &Path, keep_backup: bool, write: F) -> Result<(), LogmapError>
+where
+    F: FnOnce(&mut File) -> Result<(), LogmapError>,
+{
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name,
+        None => {
+            return Err(LogmapError::io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "path does not name a file",
+            )))
+        }
+    };
+    let directory = match path.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+
+    let (temp_path, mut temp_file) = create_temp_file(directory, file_name)?;
+    let written = write(&mut temp_file)
+        .and_then(|_| {
+            // Keep permissions of the file being replaced
+            if let Ok(metadata) = fs::metadata(path) {
+                temp_file.set_permissions(metadata.permissions())?;
+            }
+            temp_file.sync_all().map_err(LogmapError::from)
+        })
+        .and_then(|_| {
+            if keep_backup {
+                keep_backup_of(path)?;
+            }
+            fs::rename(&temp_path, path).map_err(LogmapError::from)
+        });
+    if let Err(err) = written {
+        drop(temp_file);
+        let _ = fs::remove_file(&temp_path);
+        return Err(err);
+    }
+
+    // Make the rename itself durable
+    #[cfg(unix)]
+    File::open(directory)?.sync_all()?;
+
+    Ok(())
+}
+
+/// Create new file named after `file_name` in `directory` that no one else uses
+fn create_temp_file(directory: &Path, file_name: &OsStr) -> Result<(PathBuf, File), LogmapError> {
+    let mut attempt = 0;
+    loop {
+        let mut temp_name = OsString::from(".");
+        temp_name.push(file_name);
+        temp_name.push(format!(".{}.{}.tmp", process::id(), attempt));
+        let temp_path = directory.join(temp_name);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1
+            }
+            Err(err) => return Err(LogmapError::from(err)),
+        }
+    }
+}
+
+/// Keep current content of `path` under `backup_path`, nothing to keep if `path` does not exist
+fn keep_backup_of(path: &Path) -> Result<(), LogmapError> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let backup = backup_path(path);
+    if let Err(err) = fs::remove_file(&backup) {
+        if err.kind() != io::ErrorKind::NotFound {
+            return Err(LogmapError::from(err));
+        }
+    }
+    // Hard link leaves `path` in place until it is replaced, copy where links are not supported
+    if fs::hard_link(path, &backup).is_err() {
+        fs::copy(path, &backup)?;
+    }
+
+    Ok(())
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Flag telling whether string follows, and the string itself
+fn write_optional_string(buffer: &mut Vec<u8>, string: &Option<String>) {
+    match *string {
+        Some(ref string) => {
+            buffer.push(1);
+            write_bytes(buffer, string.as_bytes());
+        }
+        None => buffer.push(0),
+    }
+}
+
+fn write_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+/// Cursor over content written by `LogFilters::save_binary_to`
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn corrupt(&self, reason: &'static str) -> LogmapError {
+        LogmapError::CorruptBinary {
+            path: None,
+            offset: self.offset,
+            reason,
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, LogmapError> {
+        match self.bytes.get(self.offset) {
+            Some(&byte) => {
+                self.offset += 1;
+                Ok(byte)
+            }
+            None => Err(self.corrupt("unexpected end of file")),
+        }
+    }
+
+    fn varint(&mut self) -> Result<u64, LogmapError> {
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift > 63 || (shift == 63 && byte > 1) {
+                return Err(self.corrupt("integer is too large"));
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Number of following elements, each of them takes at least one byte
+    fn count(&mut self) -> Result<usize, LogmapError> {
+        let count = self.varint()?;
+        if count > (self.bytes.len() - self.offset) as u64 {
+            return Err(self.corrupt("unexpected end of file"));
+        }
+        Ok(count as usize)
+    }
+
+    fn non_zero_count(&mut self) -> Result<usize, LogmapError> {
+        match self.count()? {
+            0 => Err(self.corrupt("unexpected empty list")),
+            count => Ok(count),
+        }
+    }
+
+    /// Position in a list of `len` elements
+    fn index(&mut self, len: usize) -> Result<usize, LogmapError> {
+        let index = self.varint()?;
+        if index >= len as u64 {
+            return Err(self.corrupt("index out of range"));
+        }
+        Ok(index as usize)
+    }
+
+    fn string(&mut self) -> Result<String, LogmapError> {
+        let len = self.count()?;
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(string) => {
+                self.offset += len;
+                Ok(string)
+            }
+            Err(_) => Err(self.corrupt("invalid UTF-8")),
+        }
+    }
+
+    fn optional_string(&mut self) -> Result<Option<String>, LogmapError> {
+        match self.byte()? {
+            0 => Ok(None),
+            1 => self.string().map(Some),
+            _ => Err(self.corrupt("invalid flag")),
+        }
+    }
+}
+
+/// JSON document written by `LogFilters::save_json_to`
+#[cfg(feature = "json")]
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonDocument<F> {
+    header: JsonHeader,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    filters: F,
+}
+
+#[cfg(feature = "json")]
+#[derive(Serialize, Deserialize)]
+struct JsonHeader {
+    format_version: u32,
+    max_allowed_new_alternatives: usize,
+    denote_optional: String,
+    ignore_numeric_words: bool,
+    ignore_first_columns: usize,
+    next_filter_id: FilterId,
+    /// Missing in documents written before tokenizer settings were stored
+    #[serde(default)]
+    tokenizer: BTreeMap<String, String>,
+}
+
+impl From<LogFilters> for CompiledFilters {
+    /// Build snapshot from `log_filters`, interning words of their filters
+    fn from(log_filters: LogFilters) -> Self {
+        let mut word_ids: HashMap<String, usize> = HashMap::new();
+        let mut word_filters: Vec<Vec<usize>> = Vec::new();
+        let mut columns = Vec::with_capacity(log_filters.filters.len());
+        for (filter_index, filter) in log_filters.filters.iter().enumerate() {
+            let mut filter_columns = Vec::with_capacity(filter.columns.len());
+            for column in &filter.columns {
+                let mut column_word_ids = Vec::with_capacity(column.alternatives.len());
+                for word in &column.alternatives {
+                    let word_id = *word_ids.entry(word.clone()).or_insert_with(|| {
+                        word_filters.push(Vec::new());
+                        word_filters.len() - 1
+                    });
+                    // Filters are visited in order, so indexes stay sorted
+                    if word_filters[word_id].last() != Some(&filter_index) {
+                        word_filters[word_id].push(filter_index);
+                    }
+                    column_word_ids.push(word_id);
+                }
+                column_word_ids.sort();
+                filter_columns.push(column_word_ids);
+            }
+            columns.push(filter_columns);
+        }
+        let required_columns = log_filters
+            .filters
+            .iter()
+            .map(|filter| {
+                filter
+                    .columns
+                    .iter()
+                    .filter(|column| !column.optional)
+                    .count()
+            })
+            .collect();
+
+        CompiledFilters {
+            rule_matcher: RuleMatcher::new(&log_filters.rules),
+            filters: log_filters.filters,
+            filter_indexes: log_filters.filter_indexes,
+            word_ids,
+            word_filters,
+            columns,
+            required_columns,
+            rules: log_filters.rules,
+            max_allowed_new_alternatives: log_filters.max_allowed_new_alternatives,
+            denote_optional: log_filters.denote_optional,
+            ignore_numeric_words: log_filters.ignore_numeric_words,
+            ignore_first_columns: log_filters.ignore_first_columns,
+            tokenizer: log_filters.tokenizer,
+            masks: log_filters.masks,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "tst_utils"))]
 pub mod tst_utils {
     use super::*;
 
     pub fn _words_vector_from_string(words: &str) -> Vec<String> {
-        LogFilters::line_split(words)
+        PunctuationTokenizer::default().tokens(words)
     }
 
-    pub fn _simple_filter_from_string(words: &str) -> Vec<Vec<String>> {
-        let words_vec = LogFilters::line_split(words);
+    pub fn _simple_filter_from_string(words: &str) -> Vec<Column> {
+        let words_vec = PunctuationTokenizer::default().tokens(words);
 
         let mut filter = Vec::new();
         for word in words_vec {
-            filter.push(vec![word.to_string()]);
+            filter.push(Column::new(&word));
         }
-        return filter;
+        filter
     }
 
-    pub fn _add_word_alternative(
-        mut filter: Vec<Vec<String>>,
-        index: usize,
-        word: &str,
-    ) -> Vec<Vec<String>> {
+    pub fn _add_word_alternative(mut filter: Vec<Column>, index: usize, word: &str) -> Vec<Column> {
+        if filter.get(index).is_some() {
+            filter
+                .get_mut(index)
+                .unwrap()
+                .alternatives
+                .push(word.to_string());
+            filter
+        } else {
+            panic!(
+                "Failed to create test data! Extending {:?} at {}",
+                filter, index
+            );
+        }
+    }
+
+    pub fn _set_optional(mut filter: Vec<Column>, index: usize) -> Vec<Column> {
         if filter.get(index).is_some() {
-            filter.get_mut(index).unwrap().push(word.to_string());
-            return filter;
+            filter.get_mut(index).unwrap().optional = true;
+            filter
         } else {
             panic!(
-                "Failed to create test data! Extending {:?} at {}",
+                "Failed to create test data! Marking {:?} at {} optional",
                 filter, index
             );
         }
     }
 
-    pub fn _add_test_filter(test_filters: &mut LogFilters, filter: Vec<Vec<String>>) {
+    pub fn _add_test_filter(test_filters: &mut LogFilters, filter: Vec<Column>) {
         let next_filter_index = test_filters.filters.len();
         for word_alternatives in &filter {
-            for word in word_alternatives {
-                if test_filters.words_hash.get(word).is_some() {
+            for word in &word_alternatives.alternatives {
+                if test_filters.words_hash.contains_key(word) {
                     let filter_indexes = test_filters.words_hash.get_mut(word).unwrap();
                     if !filter_indexes.contains(&next_filter_index) {
                         filter_indexes.push(next_filter_index);
@@ -701,7 +3723,12 @@ pub mod tst_utils {
                 }
             }
         }
-        test_filters.filters.push(filter);
+        let id = test_filters.take_next_filter_id();
+        test_filters.push_filter(Filter {
+            id,
+            columns: filter,
+            note: None,
+        });
     }
 
     pub fn _init_test_data() -> LogFilters {
@@ -730,40 +3757,245 @@ pub mod tst_utils {
             &mut log_filters,
             _simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv"),
         );
-        return log_filters;
+        log_filters
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use tokenizer::{RegexTokenizer, WhitespaceTokenizer};
 
     #[test]
     fn line_split() {
+        let log_filters = LogFilters::new();
         // Test if string will be splitted correctly (single separators)
         let line_1 = "a b/c,d.e:f\"g\'h(i)j{k}l[m]n";
         let result = vec![
             "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n",
         ];
-        assert_eq!(LogFilters::line_split(&line_1), result);
+        assert_eq!(log_filters.line_split(line_1), result);
 
         // Test if string will be splitted correctly (multiple separators)
         let line_2 = " /,.a:\"\'()b{}[]";
         let result = vec!["a", "b"];
-        assert_eq!(LogFilters::line_split(&line_2), result);
+        assert_eq!(log_filters.line_split(line_2), result);
 
         // Empty string expected if line consisting of only separators
         let line_3 = " /,.:\"\'(){}[]";
         let result: Vec<String> = Vec::new();
-        assert_eq!(LogFilters::line_split(&line_3), result);
+        assert_eq!(log_filters.line_split(line_3), result);
 
         let line_4 = "";
         let result: Vec<String> = Vec::new();
-        assert_eq!(LogFilters::line_split(&line_4), result);
+        assert_eq!(log_filters.line_split(line_4), result);
 
         let line_5 = "LoremIpsum";
         let result = vec!["LoremIpsum"];
-        assert_eq!(LogFilters::line_split(&line_5), result);
+        assert_eq!(log_filters.line_split(line_5), result);
+
+        // Configured delimiters replace default ones
+        let log_filters = LogFiltersConfig::new()
+            .with_word_delimiters(" =|;\t")
+            .build()
+            .unwrap();
+        assert_eq!(
+            log_filters.line_split("host=db1.example.com|user=a:b;\tv1.2"),
+            vec!["host", "db1.example.com", "user", "a:b", "v1.2"]
+        );
+    }
+
+    #[test]
+    fn word_delimiters() {
+        let mut log_filters = LogFiltersConfig::new()
+            .with_word_delimiters(" =|;\t")
+            .with_ignore_first_columns(0)
+            .build()
+            .unwrap();
+        log_filters.learn_line("host=db1.example.com|status=up");
+        assert!(log_filters.is_line_known("host=db1.example.com\tstatus;up"));
+        assert!(!log_filters.is_line_known("host=db1 example com|status=up"));
+        assert_eq!(
+            log_filters.to_string(),
+            "[host],[db1.example.com],[status],[up]"
+        );
+
+        // Delimiters are saved with filters in every format
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_to(&mut buffer).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        assert!(
+            text.contains("\ntokenizer.kind=punctuation\ntokenizer.word_delimiters=\\s=|;\\t\n")
+        );
+        // Formats this build was compiled without fail to parse
+        for format in ["text", "binary", "json"]
+            .iter()
+            .filter_map(|format| format.parse::<FileFormat>().ok())
+        {
+            let mut buffer: Vec<u8> = Vec::new();
+            log_filters.save_format_to(&mut buffer, format).unwrap();
+            let reloaded = LogFilters::load_format_from(buffer.as_slice(), format).unwrap();
+            assert_eq!(reloaded.config(), log_filters.config(), "{:?}", format);
+            assert!(reloaded.is_line_known("host=db1.example.com;status=up"));
+        }
+
+        // Loaded delimiters are kept
+        let conflicts = log_filters
+            .apply_overrides(&LogFiltersConfig::new().with_word_delimiters(" ;"))
+            .unwrap();
+        assert_eq!(
+            conflicts,
+            vec![ConfigConflict {
+                parameter: "tokenizer",
+                loaded: "punctuation(word_delimiters=\" =|;\\t\")".to_string(),
+                requested: "punctuation(word_delimiters=\" ;\")".to_string(),
+                overridden: false,
+            }]
+        );
+        assert_eq!(
+            log_filters.tokenizer().delimiters(),
+            Some(vec![' ', '=', '|', ';', '\t'])
+        );
+
+        // Words must not hold delimiters
+        assert!(log_filters.add_alternative(0, 0, "a=b").is_err());
+        assert!(log_filters.add_alternative(0, 0, "a:b").is_ok());
+    }
+
+    #[test]
+    fn tokenizer() {
+        /// Keeps only values of `key=value` pairs
+        #[derive(Debug)]
+        struct ValuesTokenizer;
+
+        impl Tokenizer for ValuesTokenizer {
+            fn tokens(&self, log_line: &str) -> Vec<String> {
+                log_line
+                    .split_whitespace()
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(_, value)| value.to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect()
+            }
+
+            fn kind(&self) -> &str {
+                "values"
+            }
+        }
+
+        let mut log_filters = LogFiltersConfig::new()
+            .with_tokenizer(ValuesTokenizer)
+            .with_ignore_first_columns(1)
+            .build()
+            .unwrap();
+        log_filters.learn_line("time=12:00:01 level=info msg=started");
+        assert_eq!(log_filters.to_string(), "[info],[started]");
+        assert!(log_filters.is_line_known("at=13:10:00 severity=info text=started"));
+        assert!(!log_filters.is_line_known("time=13:10:00 level=error msg=started"));
+        assert_eq!(
+            log_filters.filter_to_regex(log_filters.filter(0).unwrap()),
+            None
+        );
+
+        // Files of other tokenizers are written but can't be loaded back
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_to(&mut buffer).unwrap();
+        assert!(String::from_utf8(buffer.clone())
+            .unwrap()
+            .contains("\ntokenizer.kind=values\n"));
+        match LogFilters::load_from(buffer.as_slice()) {
+            Err(err) => assert_eq!(
+                err.to_string(),
+                "<input>: filters were learned with tokenizer setting `kind` = \"values\" \
+                 unknown to this build"
+            ),
+            Ok(_) => panic!("UnsupportedTokenizer expected"),
+        }
+
+        // Built-in tokenizers are loaded back
+        let mut log_filters = LogFiltersConfig::new()
+            .with_tokenizer(WhitespaceTokenizer)
+            .build()
+            .unwrap();
+        log_filters.learn_line("Oct 12 host sshd[123]: Accepted key");
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_binary_to(&mut buffer).unwrap();
+        let reloaded = LogFilters::load_binary_from(buffer.as_slice()).unwrap();
+        assert_eq!(reloaded.config(), log_filters.config());
+        assert_eq!(reloaded.to_string(), "[sshd\\[123\\]:],[Accepted],[key]");
+    }
+
+    #[test]
+    fn masks() {
+        let mut log_filters = LogFiltersConfig::new()
+            .with_masks(&[Mask::Ip, Mask::Uuid])
+            .build()
+            .unwrap();
+        log_filters.learn_line(
+            "Oct 12 host sshd[1]: session 123e4567-e89b-12d3-a456-426614174000 from 10.0.0.1",
+        );
+        log_filters.learn_line(
+            "Oct 12 host sshd[2]: session 9b2e4567-e89b-12d3-a456-426614174abc from fe80::1",
+        );
+        assert_eq!(
+            log_filters.to_string(),
+            "[sshd],[session],[<UUID>],[from],[<IP>]"
+        );
+        assert!(log_filters.is_line_known(
+            "Nov 3 other sshd[3]: session 00000000-0000-0000-0000-000000000000 from 192.168.0.1"
+        ));
+        assert!(!log_filters.is_line_known("Nov 3 other sshd[3]: session 0x7f from 192.168.0.1"));
+        // Placeholders can be used in templates, masked text of templates is replaced as well
+        log_filters.add_rule(Rule::template("kernel: link <**> from <IP>").unwrap());
+        assert!(log_filters.is_line_known("Oct 12 host kernel: link eth0 up from 10.1.1.1"));
+        log_filters.add_rule(Rule::template("kernel: gateway 10.0.0.1 <*>").unwrap());
+        assert!(log_filters.is_line_known("Oct 12 host kernel: gateway 192.168.0.1 down"));
+        assert_eq!(
+            log_filters.filter_to_regex(log_filters.filter(0).unwrap()),
+            None
+        );
+
+        // Masks are saved with filters in every format
+        for format in ["text", "binary", "json"]
+            .iter()
+            .filter_map(|format| format.parse::<FileFormat>().ok())
+        {
+            let mut buffer: Vec<u8> = Vec::new();
+            log_filters.save_format_to(&mut buffer, format).unwrap();
+            let reloaded = LogFilters::load_format_from(buffer.as_slice(), format).unwrap();
+            assert_eq!(reloaded.masks(), [Mask::Uuid, Mask::Ip], "{:?}", format);
+            assert_eq!(reloaded.to_string(), log_filters.to_string());
+        }
+
+        // Loaded masks are kept
+        let conflicts = log_filters
+            .apply_overrides(&LogFiltersConfig::new().with_masks(&Mask::ALL))
+            .unwrap();
+        assert_eq!(
+            conflicts,
+            vec![ConfigConflict {
+                parameter: "masks",
+                loaded: "\"uuid,ip\"".to_string(),
+                requested: "\"url,email,uuid,mac,ip,hex\"".to_string(),
+                overridden: false,
+            }]
+        );
+
+        // Alternatives own masks would replace never match and are dropped
+        let mut other = LogFilters::new();
+        other.learn_line("Oct 12 host sshd[1]: session 7f3a9c0d from 10.0.0.1");
+        other.learn_line("Oct 12 host sshd[1]: session 123e4567-e89b-12d3-a456-426614174000 done");
+        let conflicts = log_filters.merge(&other);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].parameter, "masks");
+        assert_eq!(
+            log_filters.to_string(),
+            "[sshd],[session],[<UUID>],[from],[<IP>],\n\
+             [sshd],[session],[7f3a9c0d],[from],\n\
+             [sshd],[session],[done]"
+        );
     }
 
     #[test]
@@ -777,26 +4009,26 @@ mod tests {
         let result = vec![
             "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n",
         ];
-        assert_eq!(log_filters.line_to_words(&line_1), result);
+        assert_eq!(log_filters.line_to_words(line_1), result);
 
         // Test if string will be splitted correctly (multiple separators)
         let line_2 = " /,.a:\"\'()b{}[]";
         let result = vec!["a", "b"];
-        assert_eq!(log_filters.line_to_words(&line_2), result);
+        assert_eq!(log_filters.line_to_words(line_2), result);
 
         // Empty string expected if line consisting of only separators
         let line_3 = " /,.:\"\'(){}[]";
         let result: Vec<String> = Vec::new();
-        assert_eq!(log_filters.line_to_words(&line_3), result);
+        assert_eq!(log_filters.line_to_words(line_3), result);
 
         let line_4 = "";
         let result: Vec<String> = Vec::new();
-        assert_eq!(log_filters.line_to_words(&line_4), result);
+        assert_eq!(log_filters.line_to_words(line_4), result);
 
         // Test if string will be splitted correctly (no separators)
         let line_5 = "LoremIpsum";
         let result = vec!["LoremIpsum"];
-        assert_eq!(log_filters.line_to_words(&line_5), result);
+        assert_eq!(log_filters.line_to_words(line_5), result);
 
         // Following tests for LogFilters::ignore_first_columns parameter set to `2`
         let mut log_filters = LogFilters::new();
@@ -806,21 +4038,21 @@ mod tests {
         // Test if string will be splitted correctly (single separators)
         let line_1 = "a b/c,d.e:f\"g\'h(i)j{k}l[m]n";
         let result = vec!["c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n"];
-        assert_eq!(log_filters.line_to_words(&line_1), result);
+        assert_eq!(log_filters.line_to_words(line_1), result);
 
         // Test if string will be splitted correctly (multiple separators)
         let line_2 = " /,.a:\"\'()b{}[]c[]{}.,";
         let result = vec!["c"];
-        assert_eq!(log_filters.line_to_words(&line_2), result);
+        assert_eq!(log_filters.line_to_words(line_2), result);
 
         // Empty string expected if line consisting of only separators
         let line_3 = " /,.:\"\'(){}[]";
         let result: Vec<String> = Vec::new();
-        assert_eq!(log_filters.line_to_words(&line_3), result);
+        assert_eq!(log_filters.line_to_words(line_3), result);
 
         let line_4 = "";
         let result: Vec<String> = Vec::new();
-        assert_eq!(log_filters.line_to_words(&line_4), result);
+        assert_eq!(log_filters.line_to_words(line_4), result);
 
         // First two words should be removed, numeric word should stay
         let line_5 = "Lorem ipsum dolor sit amet, 123 consectetur adipiscing elit7";
@@ -833,7 +4065,7 @@ mod tests {
             "adipiscing",
             "elit7",
         ];
-        assert_eq!(log_filters.line_to_words(&line_5), result);
+        assert_eq!(log_filters.line_to_words(line_5), result);
 
         // Test if numeric words will be ignored
         let mut log_filters = LogFilters::new();
@@ -843,7 +4075,7 @@ mod tests {
         // First two words and numeric word should be removed
         let line_5 = "Lorem ipsum dolor sit amet, 123 consectetur adipiscing elit7";
         let result = vec!["dolor", "sit", "amet", "consectetur", "adipiscing", "elit7"];
-        assert_eq!(log_filters.line_to_words(&line_5), result);
+        assert_eq!(log_filters.line_to_words(line_5), result);
     }
 
     #[test]
@@ -874,155 +4106,1868 @@ mod tests {
         let mut complex_filter = tst_utils::_simple_filter_from_string("eee fff ggg hhh");
         complex_filter = tst_utils::_add_word_alternative(complex_filter, 1, "iii");
         complex_filter = tst_utils::_add_word_alternative(complex_filter, 1, "jjj");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 3, ".");
+        complex_filter = tst_utils::_set_optional(complex_filter, 3);
         tst_utils::_add_test_filter(&mut log_filters, complex_filter);
         let filter_3: String = "[eee],[fff,iii,jjj],[ggg],[hhh,.]".to_string();
         let result = filter_1.clone() + ",\n" + &filter_2 + ",\n" + &filter_3;
         assert_eq!(log_filters.to_string(), result);
     }
 
+    #[test]
+    fn log_filters_config() {
+        // Unset values fall back to defaults
+        let log_filters = LogFiltersConfig::new().build().unwrap();
+        assert_eq!(log_filters.max_allowed_new_alternatives, 0);
+        assert_eq!(log_filters.denote_optional, ".");
+        assert!(log_filters.ignore_numeric_words);
+        assert_eq!(log_filters.ignore_first_columns, 2);
+
+        let log_filters = LogFiltersConfig::new()
+            .with_max_allowed_new_alternatives(3)
+            .with_denote_optional("::")
+            .with_ignore_numeric_words(false)
+            .with_ignore_first_columns(0)
+            .build()
+            .unwrap();
+        assert_eq!(log_filters.max_allowed_new_alternatives, 3);
+        assert_eq!(log_filters.denote_optional, "::");
+        assert!(!log_filters.ignore_numeric_words);
+        assert_eq!(log_filters.ignore_first_columns, 0);
+        assert_eq!(
+            log_filters.config(),
+            LogFiltersConfig::new()
+                .with_max_allowed_new_alternatives(3)
+                .with_denote_optional("::")
+                .with_ignore_numeric_words(false)
+                .with_ignore_first_columns(0)
+                .with_word_delimiters(DEFAULT_WORD_DELIMITERS)
+                .with_masks(&[])
+        );
+
+        // `denote_optional` must fit filter lines
+        for invalid in &["", " ", ".x ", ",", "[]", "\\"] {
+            match LogFiltersConfig::new()
+                .with_denote_optional(invalid)
+                .build()
+            {
+                Err(LogmapError::InvalidConfig { parameter, .. }) => {
+                    assert_eq!(parameter, "denote_optional")
+                }
+                _ => panic!("InvalidConfig expected for {:?}", invalid),
+            }
+        }
+
+        // Words equal to `denote_optional` are escaped in filter lines, any tokenizer works
+        let log_filters = LogFiltersConfig::new()
+            .with_tokenizer(WhitespaceTokenizer)
+            .with_denote_optional("x")
+            .build()
+            .unwrap();
+        assert_eq!(log_filters.tokenizer().kind(), "whitespace");
+        assert_eq!(log_filters.denote_optional, "x");
+        assert_eq!(
+            LogFiltersConfig::new().tokenizer().settings(),
+            PunctuationTokenizer::default().settings()
+        );
+        assert_eq!(
+            LogFiltersConfig::new()
+                .with_word_delimiters("")
+                .validate()
+                .unwrap_err()
+                .to_string(),
+            "invalid `word_delimiters` value \"\": cannot be empty"
+        );
+    }
+
+    #[test]
+    fn apply_overrides() {
+        let mut log_filters = LogFilters::load_from("1\n.\ntrue\n2\n[aaa]".as_bytes()).unwrap();
+        // Nothing explicitly set, nothing changes
+        let conflicts = log_filters
+            .apply_overrides(&LogFiltersConfig::new())
+            .unwrap();
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            log_filters.config(),
+            LogFilters::load_from("1\n.\ntrue\n2\n".as_bytes())
+                .unwrap()
+                .config()
+        );
+
+        // Same values as loaded do not conflict
+        let conflicts = log_filters
+            .apply_overrides(&LogFiltersConfig::new().with_max_allowed_new_alternatives(1))
+            .unwrap();
+        assert!(conflicts.is_empty());
+
+        // Explicit values win, except for `denote_optional`
+        let conflicts = log_filters
+            .apply_overrides(
+                &LogFiltersConfig::new()
+                    .with_max_allowed_new_alternatives(2)
+                    .with_denote_optional("::")
+                    .with_ignore_first_columns(0),
+            )
+            .unwrap();
+        assert_eq!(log_filters.max_allowed_new_alternatives, 2);
+        assert_eq!(log_filters.denote_optional, ".");
+        assert!(log_filters.ignore_numeric_words);
+        assert_eq!(log_filters.ignore_first_columns, 0);
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(|c| (c.parameter, c.overridden))
+                .collect::<Vec<_>>(),
+            vec![
+                ("max_allowed_new_alternatives", true),
+                ("denote_optional", false),
+                ("ignore_first_columns", true),
+            ]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "`max_allowed_new_alternatives` was loaded as 1 but 2 was requested, using 2"
+        );
+
+        // Invalid overrides are rejected
+        assert!(log_filters
+            .apply_overrides(&LogFiltersConfig::new().with_denote_optional(" "))
+            .is_err());
+    }
+
+    #[test]
+    fn save_to() {
+        let mut log_filters = LogFilters::new();
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_to(&mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "logmap-filters 2\n\
+             max_allowed_new_alternatives=0\n\
+             denote_optional=.\n\
+             ignore_numeric_words=true\n\
+             ignore_first_columns=2\n\
+             next_filter_id=0\n\
+             tokenizer.kind=punctuation\n\
+             tokenizer.word_delimiters=\\s/,.:\"'(){}[]\n\
+             tokenizer.numeric_symbols=*#\n\
+             tokenizer.masks=\n\
+             \n"
+        );
+
+        log_filters.max_allowed_new_alternatives = 1;
+        tst_utils::_add_test_filter(
+            &mut log_filters,
+            tst_utils::_simple_filter_from_string("aaa bbb"),
+        );
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_to(&mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "logmap-filters 2\n\
+             max_allowed_new_alternatives=1\n\
+             denote_optional=.\n\
+             ignore_numeric_words=true\n\
+             ignore_first_columns=2\n\
+             next_filter_id=1\n\
+             tokenizer.kind=punctuation\n\
+             tokenizer.word_delimiters=\\s/,.:\"'(){}[]\n\
+             tokenizer.numeric_symbols=*#\n\
+             tokenizer.masks=\n\
+             \n\
+             0:[aaa],[bbb]\n"
+        );
+
+        // Comments
+        log_filters.set_comment(Some("web hosts\n\nbaseline"));
+        log_filters
+            .set_note(0, Some("nightly backup, OPS-123"))
+            .unwrap();
+        tst_utils::_add_test_filter(
+            &mut log_filters,
+            tst_utils::_simple_filter_from_string("ccc"),
+        );
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_to(&mut buffer).unwrap();
+        assert!(String::from_utf8(buffer).unwrap().ends_with(
+            "tokenizer.masks=\n\
+             \n\
+             # web hosts\n\
+             #\n\
+             # baseline\n\
+             \n\
+             # nightly backup, OPS-123\n\
+             0:[aaa],[bbb]\n\
+             1:[ccc]\n"
+        ));
+    }
+
+    #[test]
+    fn load_from() {
+        let input = "1\n.\nfalse\n3\n[aaa],[bbb,ccc],\n[ddd]";
+        let log_filters = LogFilters::load_from(input.as_bytes()).unwrap();
+        assert_eq!(log_filters.max_allowed_new_alternatives, 1);
+        assert!(!log_filters.ignore_numeric_words);
+        assert_eq!(log_filters.ignore_first_columns, 3);
+        assert_eq!(log_filters.to_string(), "[aaa],[bbb,ccc],\n[ddd]");
+        assert_eq!(log_filters.words_hash.get("ccc").unwrap(), &vec![0]);
+
+        // Version 1 file is upgraded, filters saved without identifiers get new ones
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_to(&mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.starts_with("logmap-filters 2\n"));
+        assert!(output.ends_with("\n\n0:[aaa],[bbb,ccc]\n1:[ddd]\n"));
+        let log_filters_reloaded = LogFilters::load_from(output.as_bytes()).unwrap();
+        assert_eq!(log_filters_reloaded.config(), log_filters.config());
+        assert_eq!(log_filters_reloaded.filters, log_filters.filters);
+
+        // Identifiers survive round trip through `save_to`
+        let input = "1\n.\nfalse\n3\n7:[aaa],[bbb,ccc],\n3:[ddd],\n[eee]";
+        let mut log_filters = LogFilters::load_from(input.as_bytes()).unwrap();
+        let ids: Vec<FilterId> = log_filters.filters().map(Filter::id).collect();
+        assert_eq!(ids, vec![7, 3, 8]);
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_to(&mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.ends_with("\n\n7:[aaa],[bbb,ccc]\n3:[ddd]\n8:[eee]\n"));
+        let log_filters_reloaded = LogFilters::load_from(output.as_bytes()).unwrap();
+        assert_eq!(log_filters_reloaded.filters, log_filters.filters);
+        assert_eq!(
+            log_filters.learn_line("x y z fff"),
+            LearnOutcome::NewFilter { id: 9 }
+        );
+        assert_eq!(log_filters.filter_index(3), Some(1));
+        assert_eq!(log_filters.filter_id(3), Some(9));
+
+        // Duplicate identifiers are rejected
+        assert!(LogFilters::load_from("1\n.\nfalse\n3\n1:[aaa],\n1:[ddd]".as_bytes()).is_err());
+        assert!(LogFilters::load_from("1\n.\nfalse\n3\nx:[aaa]".as_bytes()).is_err());
+
+        // Errors do not refer to any file
+        let err = LogFilters::load_from("1\n.\nfalse\n3\nddd".as_bytes())
+            .err()
+            .unwrap();
+        assert!(err.path().is_none());
+        assert_eq!(err.to_string(), "<input>:5: malformed filter line");
+        let input = "logmap-filters 2\nignore_first_columns=1\n\n[aaa]\n[bbb],ccc\n";
+        let err = LogFilters::load_from(input.as_bytes()).err().unwrap();
+        assert_eq!(err.to_string(), "<input>:5: malformed filter line");
+
+        // Identifier of the next filter is kept even if filters holding it were removed
+        let input = "logmap-filters 2\nnext_filter_id=10\n\n3:[aaa]\n[bbb]\n";
+        let mut log_filters = LogFilters::load_from(input.as_bytes()).unwrap();
+        assert_eq!(log_filters.filter_id(1), Some(10));
+        assert_eq!(log_filters.config(), LogFilters::new().config());
+        log_filters.ignore_first_columns = 0;
+        assert_eq!(
+            log_filters.learn_line("x y z"),
+            LearnOutcome::NewFilter { id: 11 }
+        );
+
+        // Comments directly above a filter are its note, the rest belongs to the file
+        let input = "logmap-filters 2\n\n# file\n\n#note\n#  indented\n3:[aaa]\n\
+                     \n#  \n[bbb]\n# trailing\n";
+        let log_filters = LogFilters::load_from(input.as_bytes()).unwrap();
+        assert_eq!(log_filters.comment(), Some("file\ntrailing"));
+        assert_eq!(
+            log_filters.filter(3).unwrap().note(),
+            Some("note\n indented")
+        );
+        assert_eq!(log_filters.filter(4).unwrap().note(), Some(" "));
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_to(&mut buffer).unwrap();
+        let reloaded = LogFilters::load_from(buffer.as_slice()).unwrap();
+        assert_eq!(reloaded.filters, log_filters.filters);
+        assert_eq!(reloaded.comment(), log_filters.comment());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn save_json_to() {
+        let mut log_filters = LogFilters::new();
+        tst_utils::_add_test_filter(
+            &mut log_filters,
+            tst_utils::_set_optional(tst_utils::_simple_filter_from_string("aaa bbb"), 1),
+        );
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_json_to(&mut buffer).unwrap();
+        let document: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(
+            document,
+            serde_json::json!({
+                "header": {
+                    "format_version": 1,
+                    "max_allowed_new_alternatives": 0,
+                    "denote_optional": ".",
+                    "ignore_numeric_words": true,
+                    "ignore_first_columns": 2,
+                    "next_filter_id": 1,
+                    "tokenizer": {
+                        "kind": "punctuation",
+                        "word_delimiters": " /,.:\"'(){}[]",
+                        "numeric_symbols": "*#",
+                        "masks": ""
+                    }
+                },
+                "filters": [{
+                    "id": 0,
+                    "columns": [
+                        {"alternatives": ["aaa"], "optional": false},
+                        {"alternatives": ["bbb"], "optional": true}
+                    ]
+                }]
+            })
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn load_json_from() {
+        let mut log_filters = tst_utils::_init_test_data();
+        log_filters.set_note(1, Some("note")).unwrap();
+        log_filters.set_comment(Some("comment"));
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_json_to(&mut buffer).unwrap();
+        let reloaded = LogFilters::load_json_from(buffer.as_slice()).unwrap();
+        assert_eq!(reloaded.filters, log_filters.filters);
+        assert_eq!(reloaded.comment(), Some("comment"));
+        assert_eq!(reloaded.words_hash, log_filters.words_hash);
+        assert_eq!(reloaded.config(), log_filters.config());
+        assert_eq!(reloaded.next_filter_id, log_filters.next_filter_id);
+
+        // Unknown header entries are ignored, unsupported versions are not
+        let json = String::from_utf8(buffer).unwrap();
+        let with_extra = json.replace("\"header\": {", "\"header\": {\"added_later\": 1,");
+        assert!(LogFilters::load_json_from(with_extra.as_bytes()).is_ok());
+        let newer = json.replace("\"format_version\": 1", "\"format_version\": 2");
+        match LogFilters::load_json_from(newer.as_bytes()) {
+            Err(err @ LogmapError::UnsupportedVersion { .. }) => assert_eq!(
+                err.to_string(),
+                "<input>: file format version 2 is not supported, newest supported version is 1"
+            ),
+            _ => panic!("UnsupportedVersion expected"),
+        }
+        // Documents without tokenizer settings were split as by default settings
+        let mut without_tokenizer: serde_json::Value = serde_json::from_str(&json).unwrap();
+        without_tokenizer["header"]
+            .as_object_mut()
+            .unwrap()
+            .remove("tokenizer");
+        assert!(LogFilters::load_json_from(without_tokenizer.to_string().as_bytes()).is_ok());
+        let other_tokenizer =
+            json.replace("\"numeric_symbols\": \"*#\"", "\"numeric_symbols\": \"\"");
+        match LogFilters::load_json_from(other_tokenizer.as_bytes()) {
+            Err(LogmapError::UnsupportedTokenizer { setting, .. }) => {
+                assert_eq!(setting, "numeric_symbols")
+            }
+            _ => panic!("UnsupportedTokenizer expected"),
+        }
+        // Malformed documents and invalid filters are rejected
+        match LogFilters::load_json_from("[aaa]".as_bytes()) {
+            Err(err @ LogmapError::Json { .. }) => {
+                assert!(err
+                    .to_string()
+                    .starts_with("<input>: invalid JSON document"))
+            }
+            _ => panic!("Json error expected"),
+        }
+        let duplicated = json.replace("\"id\": 1,", "\"id\": 0,");
+        match LogFilters::load_json_from(duplicated.as_bytes()) {
+            Err(LogmapError::InvalidFilter { id, .. }) => assert_eq!(id, 0),
+            _ => panic!("InvalidFilter expected"),
+        }
+    }
+
+    #[test]
+    fn save_binary_to() {
+        let mut log_filters = LogFilters::new();
+        tst_utils::_add_test_filter(
+            &mut log_filters,
+            tst_utils::_set_optional(tst_utils::_simple_filter_from_string("aaa bbb aaa"), 1),
+        );
+        log_filters.set_note(0, Some("n")).unwrap();
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_binary_to(&mut buffer).unwrap();
+        let mut expected = b"LOGMAP\0B".to_vec();
+        // version, parameters, no comment
+        expected.extend_from_slice(&[1, 0, 1, b'.', 1, 2, 1, 0]);
+        // tokenizer settings
+        expected.push(4);
+        expected.push(4);
+        expected.extend_from_slice(b"kind");
+        expected.push(11);
+        expected.extend_from_slice(b"punctuation");
+        expected.push(15);
+        expected.extend_from_slice(b"word_delimiters");
+        expected.push(13);
+        expected.extend_from_slice(b" /,.:\"'(){}[]");
+        expected.push(15);
+        expected.extend_from_slice(b"numeric_symbols");
+        expected.extend_from_slice(&[2, b'*', b'#']);
+        expected.push(5);
+        expected.extend_from_slice(b"masks");
+        expected.push(0);
+        // word table
+        expected.extend_from_slice(&[2, 3, b'a', b'a', b'a', 3, b'b', b'b', b'b']);
+        // filter 0 with three columns, second one optional, and a note
+        expected.extend_from_slice(&[1, 0, 3, 0, 1, 0, 1, 1, 1, 0, 1, 0, 1, 1, b'n']);
+        // index
+        expected.extend_from_slice(&[2, 0, 1, 0, 1, 1, 0]);
+        assert_eq!(buffer, expected);
+
+        // Same filters, same output
+        let mut other_buffer: Vec<u8> = Vec::new();
+        log_filters.save_binary_to(&mut other_buffer).unwrap();
+        assert_eq!(other_buffer, buffer);
+    }
+
+    #[test]
+    fn load_binary_from() {
+        let mut log_filters = tst_utils::_init_test_data();
+        log_filters.set_column_optional(2, 1, true).unwrap();
+        log_filters.add_alternative(2, 1, "ąę").unwrap();
+        log_filters.remove_filter(1).unwrap();
+        log_filters.set_note(3, Some("first\nsecond")).unwrap();
+        log_filters.set_comment(Some("comment"));
+        let mut buffer: Vec<u8> = Vec::new();
+        log_filters.save_binary_to(&mut buffer).unwrap();
+        let reloaded = LogFilters::load_binary_from(buffer.as_slice()).unwrap();
+        assert_eq!(reloaded.filters, log_filters.filters);
+        assert_eq!(reloaded.comment(), Some("comment"));
+        assert_eq!(reloaded.words_hash, log_filters.words_hash);
+        assert_eq!(reloaded.config(), log_filters.config());
+        assert_eq!(reloaded.next_filter_id, log_filters.next_filter_id);
+
+        // Truncated file is never accepted
+        for len in 0..buffer.len() {
+            assert!(LogFilters::load_binary_from(&buffer[..len]).is_err());
+        }
+        // Text file is not mistaken for binary one
+        match LogFilters::load_binary_from("logmap-filters 2\n\n".as_bytes()) {
+            Err(LogmapError::CorruptBinary { offset, .. }) => assert_eq!(offset, 0),
+            _ => panic!("CorruptBinary expected"),
+        }
+        // Unknown version
+        let mut newer = buffer.clone();
+        newer[BINARY_MAGIC.len()] = 2;
+        match LogFilters::load_binary_from(newer.as_slice()) {
+            Err(err @ LogmapError::UnsupportedVersion { .. }) => assert_eq!(
+                err.to_string(),
+                "<input>: file format version 2 is not supported, newest supported version is 1"
+            ),
+            _ => panic!("UnsupportedVersion expected"),
+        }
+        let mut newest = BINARY_MAGIC.to_vec();
+        write_varint(&mut newest, u64::MAX);
+        match LogFilters::load_binary_from(newest.as_slice()) {
+            Err(LogmapError::UnsupportedVersion { version, .. }) => assert_eq!(version, u64::MAX),
+            _ => panic!("UnsupportedVersion expected"),
+        }
+        // Tokenizer settings this build can't reproduce
+        let mut tokenizer = b"LOGMAP\0B".to_vec();
+        tokenizer.extend_from_slice(&[1, 0, 1, b'.', 1, 2, 1, 0]);
+        tokenizer.extend_from_slice(&[1, 15]);
+        tokenizer.extend_from_slice(b"numeric_symbols");
+        tokenizer.extend_from_slice(&[1, b'*', 0, 0, 0]);
+        match LogFilters::load_binary_from(tokenizer.as_slice()) {
+            Err(LogmapError::UnsupportedTokenizer { setting, .. }) => {
+                assert_eq!(setting, "numeric_symbols")
+            }
+            _ => panic!("UnsupportedTokenizer expected"),
+        }
+        // Trailing data
+        let mut longer = buffer.clone();
+        longer.push(0);
+        match LogFilters::load_binary_from(longer.as_slice()) {
+            Err(err @ LogmapError::CorruptBinary { .. }) => assert_eq!(
+                err.to_string(),
+                format!(
+                    "<input>: file is corrupted at byte {}: unexpected data after index",
+                    buffer.len()
+                )
+            ),
+            _ => panic!("CorruptBinary expected"),
+        }
+    }
+
+    /// Empty directory unique to the calling test
+    fn _test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("logmap-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn _dir_entries(dir: &Path) -> Vec<String> {
+        let mut entries: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn save_as() {
+        let dir = _test_dir("save_as");
+        let path = dir.join("filters");
+        let log_filters = tst_utils::_init_test_data();
+        log_filters.save_as(&path, FileFormat::Text).unwrap();
+        let reloaded = LogFilters::load(&path).unwrap();
+        assert_eq!(reloaded.filters, log_filters.filters);
+        assert_eq!(_dir_entries(&dir), vec!["filters"]);
+
+        // Previous version is kept only when asked for
+        LogFilters::new()
+            .save_with_backup(&path, FileFormat::Text)
+            .unwrap();
+        assert_eq!(_dir_entries(&dir), vec!["filters", "filters.bak"]);
+        assert!(LogFilters::load(&path).unwrap().is_empty());
+        let backup = LogFilters::load(&backup_path(&path)).unwrap();
+        assert_eq!(backup.filters, log_filters.filters);
+        log_filters
+            .save_with_backup(&path, FileFormat::Text)
+            .unwrap();
+        assert!(LogFilters::load(&backup_path(&path)).unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compression() {
+        assert_eq!(
+            Compression::from_path(Path::new("a/filters.gz")),
+            Compression::Gzip
+        );
+        assert_eq!(
+            Compression::from_path(Path::new("filters.zst")),
+            Compression::Zstd
+        );
+        assert_eq!(
+            Compression::from_path(Path::new("filters.gz.txt")),
+            Compression::None
+        );
+        assert_eq!(Compression::from_path(Path::new("gz")), Compression::None);
+        assert_eq!(
+            Compression::detect(b"logmap-filters 2\n"),
+            Compression::None
+        );
+        assert_eq!(Compression::detect(b""), Compression::None);
+
+        let dir = _test_dir("compression");
+        let log_filters = tst_utils::_init_test_data();
+        for &(name, compression, enabled) in &[
+            ("filters.gz", Compression::Gzip, cfg!(feature = "gzip")),
+            ("filters.zst", Compression::Zstd, cfg!(feature = "zstd")),
+        ] {
+            let path = dir.join(name);
+            let saved = log_filters.save_as(&path, FileFormat::Binary);
+            if !enabled {
+                match saved {
+                    Err(LogmapError::UnsupportedCompression { .. }) => continue,
+                    _ => panic!("UnsupportedCompression expected"),
+                }
+            }
+            saved.unwrap();
+            assert_eq!(Compression::detect(&fs::read(&path).unwrap()), compression);
+
+            // Content decides, not the name
+            let renamed = dir.join("filters");
+            fs::rename(&path, &renamed).unwrap();
+            let reloaded = LogFilters::load_as(&renamed, FileFormat::Binary).unwrap();
+            assert_eq!(reloaded.filters, log_filters.filters);
+            assert_eq!(reloaded.words_hash, log_filters.words_hash);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn write_atomically() {
+        let dir = _test_dir("write_atomically");
+        let path = dir.join("filters");
+        super::write_atomically(&path, false, |file| {
+            file.write_all(b"old").map_err(LogmapError::from)
+        })
+        .unwrap();
+
+        // Failed write leaves previous content and no temporary file behind
+        for &keep_backup in &[false, true] {
+            let result = super::write_atomically(&path, keep_backup, |file| {
+                file.write_all(b"new")?;
+                Err(LogmapError::InvalidWord {
+                    word: "new".to_string(),
+                })
+            });
+            assert!(result.is_err());
+            assert_eq!(fs::read(&path).unwrap(), b"old");
+            assert_eq!(_dir_entries(&dir), vec!["filters"]);
+        }
+
+        // Directory can't be replaced
+        match super::write_atomically(&dir, false, |_| Ok(())) {
+            Err(LogmapError::Io { .. }) => {}
+            _ => panic!("Io error expected"),
+        }
+        assert_eq!(_dir_entries(&dir), vec!["filters"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_format() {
+        assert_eq!("text".parse::<FileFormat>().unwrap(), FileFormat::Text);
+        #[cfg(feature = "json")]
+        assert_eq!("json".parse::<FileFormat>().unwrap(), FileFormat::Json);
+        assert_eq!("binary".parse::<FileFormat>().unwrap(), FileFormat::Binary);
+        assert!("yaml".parse::<FileFormat>().is_err());
+
+        assert_eq!(FileFormat::detect(b"logmap-filters 2\n"), FileFormat::Text);
+        assert_eq!(FileFormat::detect(b"2\n.\n"), FileFormat::Text);
+        assert_eq!(FileFormat::detect(b""), FileFormat::Text);
+        assert_eq!(FileFormat::detect(BINARY_MAGIC), FileFormat::Binary);
+        #[cfg(feature = "json")]
+        assert_eq!(FileFormat::detect(b"{\"header\":"), FileFormat::Json);
+
+        // Files of every format load without naming it
+        let log_filters = tst_utils::_init_test_data();
+        for format in ["text", "binary", "json"]
+            .iter()
+            .filter_map(|format| format.parse::<FileFormat>().ok())
+        {
+            let mut buffer: Vec<u8> = Vec::new();
+            log_filters.save_format_to(&mut buffer, format).unwrap();
+            let reloaded = LogFilters::load_detected_from(buffer.as_slice(), None).unwrap();
+            assert_eq!(reloaded.filters, log_filters.filters, "{:?}", format);
+        }
+    }
+
     #[test]
     fn load_parameters() {
-        // TODO: cover incorrect input
+        let log_filters_lines = vec![
+            "logmap-filters 2",
+            "ignore_numeric_words=false",
+            "denote_optional=::",
+            "some_future_parameter=x",
+            "max_allowed_new_alternatives=3",
+            "next_filter_id=7",
+            "",
+            "[aaa]",
+        ];
+        let (log_filters, header_lines) = LogFilters::load_parameters(&log_filters_lines).unwrap();
+        assert_eq!(header_lines, 7);
+        assert_eq!(log_filters.max_allowed_new_alternatives, 3);
+        assert_eq!(log_filters.denote_optional, "::");
+        assert!(!log_filters.ignore_numeric_words);
+        // Missing parameters fall back to defaults
+        assert_eq!(
+            log_filters.ignore_first_columns,
+            DEFAULT_IGNORE_FIRST_COLUMNS
+        );
+        assert_eq!(log_filters.next_filter_id, 7);
+
+        // Newer or unknown versions are rejected
+        match LogFilters::load_parameters(&["logmap-filters 3", ""]) {
+            Err(LogmapError::UnsupportedVersion { version, .. }) => assert_eq!(version, 3),
+            _ => panic!("UnsupportedVersion expected"),
+        }
+        match LogFilters::load_parameters(&["logmap-filters two", ""]) {
+            Err(LogmapError::CorruptHeader { line, .. }) => assert_eq!(line, 1),
+            _ => panic!("CorruptHeader expected"),
+        }
+        // Header must end with an empty line
+        match LogFilters::load_parameters(&["logmap-filters 2", "denote_optional=."]) {
+            Err(LogmapError::CorruptHeader { line, .. }) => assert_eq!(line, 3),
+            _ => panic!("CorruptHeader expected"),
+        }
+        // Tokenizer settings must match the ones `line_to_words` uses
+        let (log_filters, _) = LogFilters::load_parameters(&[
+            "logmap-filters 2",
+            "tokenizer.numeric_symbols=*#",
+            "tokenizer.word_delimiters=\\s/,.:\"'(){}[]",
+            "",
+        ])
+        .unwrap();
+        assert_eq!(log_filters.config(), LogFilters::new().config());
+        let (log_filters, _) = LogFilters::load_parameters(&[
+            "logmap-filters 2",
+            "tokenizer.kind=regex",
+            "tokenizer.pattern=[\\s=]+",
+            "",
+        ])
+        .unwrap();
+        assert_eq!(log_filters.line_split("a=b c"), vec!["a", "b", "c"]);
+        for (lines, message) in &[
+            (
+                ["logmap-filters 2", "tokenizer.kind=custom", ""],
+                "<input>: filters were learned with tokenizer setting `kind` = \"custom\" \
+                 unknown to this build",
+            ),
+            (
+                ["logmap-filters 2", "tokenizer.pattern=-", ""],
+                "<input>: filters were learned with tokenizer setting `pattern` = \"-\" \
+                 unknown to this build",
+            ),
+            (
+                ["logmap-filters 2", "tokenizer.numeric_symbols=*", ""],
+                "<input>: filters were learned with tokenizer setting `numeric_symbols` = \"*\", \
+                 this build uses \"*#\"",
+            ),
+            (
+                ["logmap-filters 2", "tokenizer.lowercase=true", ""],
+                "<input>: filters were learned with tokenizer setting `lowercase` = \"true\" \
+                 unknown to this build",
+            ),
+            (
+                ["logmap-filters 2", "tokenizer.masks=ip,guid", ""],
+                "<input>: filters were learned with tokenizer setting `masks` = \"ip,guid\" \
+                 unknown to this build",
+            ),
+        ] {
+            match LogFilters::load_parameters(lines) {
+                Err(err @ LogmapError::UnsupportedTokenizer { .. }) => {
+                    assert_eq!(err.to_string(), *message)
+                }
+                _ => panic!("UnsupportedTokenizer expected for {:?}", lines),
+            }
+        }
+
+        // Malformed, repeated or invalid parameters
+        for &(ref lines, bad_line) in &[
+            (vec!["logmap-filters 2", "ignore_first_columns", ""], 2),
+            (vec!["logmap-filters 2", "x=1", "x=1", ""], 3),
+            (vec!["logmap-filters 2", "denote_optional=[", ""], 2),
+            (vec!["logmap-filters 2", "ignore_numeric_words=yes", ""], 2),
+            (vec!["logmap-filters 2", "next_filter_id=-1", ""], 2),
+            (
+                vec!["logmap-filters 2", "tokenizer.word_delimiters=\\x", ""],
+                2,
+            ),
+        ] {
+            match LogFilters::load_parameters(lines) {
+                Err(LogmapError::BadParameter { line, .. }) => assert_eq!(line, bad_line),
+                _ => panic!("BadParameter expected for {:?}", lines),
+            }
+        }
+    }
+
+    #[test]
+    fn load_v1_parameters() {
         let log_filters_lines = vec!["2", ".", "true", "2", "0"];
-        let log_filters = LogFilters::load_parameters(&log_filters_lines);
+        let log_filters = LogFilters::load_v1_parameters(&log_filters_lines).unwrap();
         assert_eq!(log_filters.max_allowed_new_alternatives, 2);
         assert_eq!(log_filters.denote_optional, ".");
-        assert_eq!(log_filters.ignore_numeric_words, true);
+        assert!(log_filters.ignore_numeric_words);
         assert_eq!(log_filters.ignore_first_columns, 2);
+
+        // Too few lines
+        let log_filters_lines = vec!["2", ".", "true"];
+        match LogFilters::load_v1_parameters(&log_filters_lines) {
+            Err(LogmapError::CorruptHeader { line, .. }) => assert_eq!(line, 4),
+            _ => panic!("CorruptHeader expected"),
+        }
+        // Empty optional marker
+        let log_filters_lines = vec!["2", "", "true", "2", "0"];
+        match LogFilters::load_v1_parameters(&log_filters_lines) {
+            Err(LogmapError::CorruptHeader { line, found, .. }) => {
+                assert_eq!(line, 2);
+                assert_eq!(found, "");
+            }
+            _ => panic!("CorruptHeader expected"),
+        }
+        // Unparsable parameters
+        let log_filters_lines = vec!["two", ".", "true", "2", "0"];
+        match LogFilters::load_v1_parameters(&log_filters_lines) {
+            Err(LogmapError::BadParameter { line, value, .. }) => {
+                assert_eq!(line, 1);
+                assert_eq!(value, "two");
+            }
+            _ => panic!("BadParameter expected"),
+        }
+        let log_filters_lines = vec!["2", ".", "yes", "2", "0"];
+        match LogFilters::load_v1_parameters(&log_filters_lines) {
+            Err(LogmapError::BadParameter { line, value, .. }) => {
+                assert_eq!(line, 3);
+                assert_eq!(value, "yes");
+            }
+            _ => panic!("BadParameter expected"),
+        }
+        let log_filters_lines = vec!["2", ".", "true", "-2", "0"];
+        match LogFilters::load_v1_parameters(&log_filters_lines) {
+            Err(LogmapError::BadParameter { line, .. }) => assert_eq!(line, 4),
+            _ => panic!("BadParameter expected"),
+        }
+    }
+
+    #[test]
+    fn parse_columns() {
+        let log_filters = LogFilters::new();
+        let mut expected = tst_utils::_simple_filter_from_string("a c");
+        expected = tst_utils::_add_word_alternative(expected, 0, "b");
+        expected = tst_utils::_set_optional(expected, 1);
+        assert_eq!(
+            log_filters.parse_columns("[a,b],[c,.]", true),
+            Some(expected.clone())
+        );
+        // Trailing `,` of version 1 files
+        assert_eq!(
+            log_filters.parse_columns("[a,b],[c,.],", false),
+            Some(expected)
+        );
+        for malformed in &[
+            "", "[", "[a", "[a]]", "[a],b", "[a][b]", "[[a]]", "[a]x", "[.]", "[]", "[a\\",
+        ] {
+            assert_eq!(
+                log_filters.parse_columns(malformed, true),
+                None,
+                "{:?}",
+                malformed
+            );
+        }
+        // Escaped characters belong to the word
+        let columns = log_filters
+            .parse_columns("[\\[a\\,b\\]],[\\.,\\\\],[x\\ny\\r]", true)
+            .unwrap();
+        assert_eq!(columns[0].alternatives(), ["[a,b]"]);
+        assert_eq!(columns[1].alternatives(), [".", "\\"]);
+        assert!(!columns[1].is_optional());
+        assert_eq!(columns[2].alternatives(), ["x\ny\r"]);
+        // Backslash is a regular character of version 1 files
+        let columns = log_filters.parse_columns("[c\\d],[\\.]", false).unwrap();
+        assert_eq!(columns[0].alternatives(), ["c\\d"]);
+        assert_eq!(columns[1].alternatives(), ["\\."]);
+    }
+
+    /// `regex` as read by the `regex` crate, which needs `[` escaped within bracket expressions
+    fn posix_to_regex(regex: &str) -> Regex {
+        let mut converted = String::new();
+        let mut chars = regex.chars().peekable();
+        while let Some(c) = chars.next() {
+            converted.push(c);
+            match c {
+                '\\' => converted.extend(chars.next()),
+                '[' => {
+                    if chars.peek() == Some(&'^') {
+                        converted.extend(chars.next());
+                    }
+                    // `]` right at the start is a plain character
+                    if chars.peek() == Some(&']') {
+                        converted.extend(chars.next());
+                    }
+                    for c in chars.by_ref() {
+                        match c {
+                            ']' => break,
+                            '[' | '\\' => converted.push('\\'),
+                            _ => {}
+                        }
+                        converted.push(c);
+                    }
+                    converted.push(']');
+                }
+                _ => {}
+            }
+        }
+
+        Regex::new(&converted).unwrap()
+    }
+
+    /// Whether `grep` with `flavour` of expressions finds any of `regexes` in `line`,
+    /// `None` if it can't be run
+    fn grep(flavour: &str, regexes: &[String], line: &str) -> Option<bool> {
+        // `grep -P` takes a single pattern only
+        let regexes: Vec<String> = regexes.iter().map(|regex| format!("({})", regex)).collect();
+        let mut grep = Command::new("grep")
+            .arg("-q")
+            .arg(flavour)
+            .arg("-e")
+            .arg(regexes.join("|"))
+            .stdin(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        grep.stdin
+            .take()
+            .unwrap()
+            .write_all(format!("{}\n", line).as_bytes())
+            .unwrap();
+        match grep.wait().unwrap().code() {
+            Some(0) => Some(true),
+            Some(1) => Some(false),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn filter_to_regex() {
+        let mut log_filters = LogFilters::new();
+        log_filters.learn_line("Oct 12 host kernel: usb 1-1: new device");
+        log_filters.learn_line("Oct 12 host sshd[123]: Accepted key for root");
+        log_filters.set_column_optional(0, 3, true).unwrap();
+        log_filters.add_alternative(1, 2, "password").unwrap();
+        log_filters.add_alternative(1, 3, "a+b$").unwrap();
+        assert_eq!(
+            log_filters
+                .filter_to_regex(log_filters.filter(1).unwrap())
+                .unwrap(),
+            "^[] /,.:\"'(){}[]*([0-9*#]+([] /,.:\"'(){}[]+|$))*\
+             ([^] /,.:\"'(){}[]*[^]0-9 /,.:\"'(){}*#[][^] /,.:\"'(){}[]*\
+             ([] /,.:\"'(){}[]+([0-9*#]+([] /,.:\"'(){}[]+|$))*|$)){2}\
+             (sshd)([] /,.:\"'(){}[]+([0-9*#]+([] /,.:\"'(){}[]+|$))*|$)\
+             (Accepted)([] /,.:\"'(){}[]+([0-9*#]+([] /,.:\"'(){}[]+|$))*|$)\
+             (key|password)([] /,.:\"'(){}[]+([0-9*#]+([] /,.:\"'(){}[]+|$))*|$)\
+             (for|a\\+b\\$)([] /,.:\"'(){}[]+([0-9*#]+([] /,.:\"'(){}[]+|$))*|$)\
+             (root)([] /,.:\"'(){}[]+([0-9*#]+([] /,.:\"'(){}[]+|$))*|$)$"
+        );
+
+        // Same lines match as with `is_line_known` when no new alternatives are allowed
+        let lines = [
+            "Oct 12 host kernel: usb 1-1: new device",
+            "Nov 3 other kernel: usb 1-1: device",
+            "Nov 3 other kernel: usb 1-1: new device.",
+            "Nov 3 other kernel usb 1-1 new device extra",
+            "Nov 3 other kernel: usb 1-1: old device",
+            "Nov 3 other kernel: usbx 1-1: new device",
+            "Nov 3 other kernel: usb 2-7: new device",
+            "[Nov] 3 12:00 host kernel: usb 1-1: new device",
+            "Oct 12 host sshd[9]: Accepted password for root",
+            "Oct 12 host sshd[123]: Accepted password a+b$ root",
+            "Oct 12 host sshd[123]: Accepted password ab root",
+            "12 Oct 12 host sshd[123]: Accepted key for root 22",
+            "Oct 12 host sshd[123]: Accepted key for root x",
+            "host sshd[123]: Accepted key for root",
+            "",
+        ];
+        // `None` stands for `WhitespaceTokenizer`
+        for &(ignore_numeric_words, ignore_first_columns, word_delimiters) in &[
+            (true, 2, Some(DEFAULT_WORD_DELIMITERS)),
+            (false, 3, Some(DEFAULT_WORD_DELIMITERS)),
+            (true, 0, Some(DEFAULT_WORD_DELIMITERS)),
+            (true, 2, Some(" :[]-")),
+            (true, 2, None),
+        ] {
+            let config = LogFiltersConfig::new()
+                .with_ignore_numeric_words(ignore_numeric_words)
+                .with_ignore_first_columns(ignore_first_columns);
+            let mut log_filters = match word_delimiters {
+                Some(word_delimiters) => config.with_word_delimiters(word_delimiters),
+                None => config.with_tokenizer(WhitespaceTokenizer),
+            }
+            .build()
+            .unwrap();
+            log_filters.learn_line(lines[0]);
+            log_filters.learn_line("Oct 12 host sshd[123]: Accepted key for root");
+            let column_of = |id: FilterId, word: &str| {
+                log_filters
+                    .filter(id)
+                    .unwrap()
+                    .columns()
+                    .iter()
+                    .position(|column| column.contains(word))
+                    .unwrap()
+            };
+            let (new_column, key_column, for_column) = (
+                column_of(0, "new"),
+                column_of(1, "key"),
+                column_of(1, "for"),
+            );
+            log_filters
+                .set_column_optional(0, new_column, true)
+                .unwrap();
+            log_filters
+                .add_alternative(1, key_column, "password")
+                .unwrap();
+            log_filters.add_alternative(1, for_column, "a+b$").unwrap();
+
+            let regexes: Vec<String> = log_filters
+                .filters()
+                .map(|filter| log_filters.filter_to_regex(filter).unwrap())
+                .collect();
+            let compiled: Vec<Regex> = regexes.iter().map(|regex| posix_to_regex(regex)).collect();
+            let mut known_lines = 0;
+            for line in &lines {
+                let known = log_filters.is_line_known(line);
+                assert_eq!(
+                    compiled.iter().any(|regex| regex.is_match(line)),
+                    known,
+                    "{:?} with {:?}",
+                    line,
+                    (ignore_numeric_words, ignore_first_columns, word_delimiters)
+                );
+                // Also checked with engines the expressions are meant for, where installed
+                for flavour in &["-E", "-P"] {
+                    if grep(flavour, &["x".to_string()], "x") == Some(true) {
+                        assert_eq!(
+                            grep(flavour, &regexes, line),
+                            Some(known),
+                            "{:?} with grep {} and {:?}",
+                            line,
+                            flavour,
+                            (ignore_numeric_words, ignore_first_columns, word_delimiters)
+                        );
+                    }
+                }
+                known_lines += known as usize;
+            }
+            assert!(known_lines > 2);
+        }
+
+        // Numeric words of other digits than ASCII ones are skipped by `is_line_known` only
+        let mut log_filters = LogFilters::new();
+        log_filters.learn_line("Oct 12 host app started 3 workers");
+        let regex = log_filters
+            .filter_to_regex(log_filters.filter(0).unwrap())
+            .unwrap();
+        for &(line, exported) in &[
+            ("Oct 12 host app started 7 workers", true),
+            ("Oct 12 host app started \u{663} workers", false),
+        ] {
+            assert!(log_filters.is_line_known(line));
+            assert_eq!(
+                posix_to_regex(&regex).is_match(line),
+                exported,
+                "{:?}",
+                line
+            );
+        }
+
+        // Tokenizers splitting on more than single characters can't be exported
+        let mut log_filters = LogFiltersConfig::new()
+            .with_tokenizer(RegexTokenizer::new(r"\s+").unwrap())
+            .build()
+            .unwrap();
+        log_filters.learn_line(lines[0]);
+        assert_eq!(
+            log_filters.filter_to_regex(log_filters.filter(0).unwrap()),
+            None
+        );
+        // Bracket expressions read the same in POSIX and Perl compatible engines
+        assert_eq!(regex_class(&['-', ']', 'a', '['], true, false), "[]0-9a[-]");
+        assert_eq!(regex_class(&['^', '\\'], false, true), "[^\\\\^]");
+        assert_eq!(regex_class(&['^'], false, false), "\\^");
+        assert_eq!(regex_class(&['-', '^'], false, false), "[-^]");
+        assert_eq!(regex_escape("a.b]c{1}^\\"), "a\\.b]c\\{1}\\^\\\\");
+    }
+
+    #[test]
+    fn escape_word() {
+        let log_filters = LogFilters::new();
+        assert_eq!(log_filters.escape_word("abc"), "abc");
+        assert_eq!(log_filters.escape_word("[a,b]"), "\\[a\\,b\\]");
+        assert_eq!(log_filters.escape_word("C:\\x"), "C:\\\\x");
+        assert_eq!(log_filters.escape_word("a\nb\r"), "a\\nb\\r");
+        assert_eq!(log_filters.escape_word("."), "\\.");
+        assert_eq!(log_filters.escape_word(".."), "..");
+        let log_filters = LogFiltersConfig::new()
+            .with_denote_optional("::")
+            .build()
+            .unwrap();
+        assert_eq!(log_filters.escape_word("."), ".");
+        assert_eq!(log_filters.escape_word("::"), "\\::");
+
+        // Any word survives save and load
+        let words = [
+            ".", "::", "[", "]", ",", "\\", "\\.", "a,b", "[x]", "\\n", "a\nb", "\r", " ", "a b",
+            ":", "1:[a]",
+        ];
+        for denote_optional in &[".", "::"] {
+            let mut log_filters = LogFiltersConfig::new()
+                .with_denote_optional(denote_optional)
+                .build()
+                .unwrap();
+            let mut filter = tst_utils::_simple_filter_from_string("aaa bbb");
+            for word in words.iter() {
+                filter = tst_utils::_add_word_alternative(filter, 0, word);
+                tst_utils::_add_test_filter(&mut log_filters, vec![Column::new(word)]);
+            }
+            filter = tst_utils::_set_optional(filter, 0);
+            tst_utils::_add_test_filter(&mut log_filters, filter);
+            let mut buffer: Vec<u8> = Vec::new();
+            log_filters.save_to(&mut buffer).unwrap();
+            let reloaded = LogFilters::load_from(buffer.as_slice()).unwrap();
+            assert_eq!(reloaded.filters, log_filters.filters);
+            assert_eq!(reloaded.words_hash, log_filters.words_hash);
+        }
     }
 
     #[test]
     fn from_str_lines() {
-        // TODO: cover incorrect input
+        // Malformed filter lines are reported with their line number
+        let mut log_filters = LogFilters::new();
+        match log_filters.from_str_lines(&["[a],[b]", "", "[a],b"], 5, true) {
+            Err(LogmapError::BadFilterLine { line_no, .. }) => assert_eq!(line_no, 7),
+            _ => panic!("BadFilterLine expected"),
+        }
+        let mut log_filters = LogFilters::new();
+        assert!(log_filters.from_str_lines(&["[a],[[b]"], 1, true).is_err());
+        let mut log_filters = LogFilters::new();
+        assert!(log_filters.from_str_lines(&["a b c"], 1, true).is_err());
+
         // Filter with no alternatives
         let log_filters_lines = vec!["[a],[b],[c],[d],[e]"];
         let mut log_filters = LogFilters::new();
         log_filters.max_allowed_new_alternatives = 1;
         log_filters.ignore_numeric_words = true;
         log_filters.ignore_first_columns = 2;
-        log_filters.from_str_lines(&log_filters_lines);
+        log_filters
+            .from_str_lines(&log_filters_lines, 1, true)
+            .unwrap();
+        assert_eq!(log_filters.filters.len(), 1);
+        let expected = tst_utils::_simple_filter_from_string("a b c d e");
+        assert_eq!(log_filters.filters[0].columns, expected);
+        assert_eq!(log_filters.words_hash.get("a").unwrap(), &vec![0_usize]);
+        assert_eq!(log_filters.words_hash.get("b").unwrap(), &vec![0_usize]);
+        assert_eq!(log_filters.words_hash.get("c").unwrap(), &vec![0_usize]);
+        assert_eq!(log_filters.words_hash.get("d").unwrap(), &vec![0_usize]);
+        assert_eq!(log_filters.words_hash.get("e").unwrap(), &vec![0_usize]);
+
+        // Filter with alternatives
+        let log_filters_lines = vec!["[a,b],[c],[d,e]"];
+        let mut log_filters = LogFilters::new();
+        log_filters.max_allowed_new_alternatives = 1;
+        log_filters.ignore_numeric_words = true;
+        log_filters.ignore_first_columns = 2;
+        log_filters
+            .from_str_lines(&log_filters_lines, 1, true)
+            .unwrap();
         assert_eq!(log_filters.filters.len(), 1);
-        let expected = tst_utils::_simple_filter_from_string("a b c d e");
-        assert_eq!(log_filters.filters[0], expected);
+        let mut expected = tst_utils::_simple_filter_from_string("a c d");
+        expected = tst_utils::_add_word_alternative(expected, 0, "b");
+        expected = tst_utils::_add_word_alternative(expected, 2, "e");
+        assert_eq!(log_filters.filters[0].columns, expected);
+        assert_eq!(log_filters.words_hash.get("a").unwrap(), &vec![0_usize]);
+        assert_eq!(log_filters.words_hash.get("b").unwrap(), &vec![0_usize]);
+        assert_eq!(log_filters.words_hash.get("c").unwrap(), &vec![0_usize]);
+        assert_eq!(log_filters.words_hash.get("d").unwrap(), &vec![0_usize]);
+        assert_eq!(log_filters.words_hash.get("e").unwrap(), &vec![0_usize]);
+
+        // Two filters
+        let log_filters_lines = vec!["[a],[b],[c],[d],[e,f]", "[a,b],[c],[d,e,g]"];
+        let mut log_filters = LogFilters::new();
+        log_filters.max_allowed_new_alternatives = 1;
+        log_filters.ignore_numeric_words = true;
+        log_filters.ignore_first_columns = 2;
+        log_filters
+            .from_str_lines(&log_filters_lines, 1, true)
+            .unwrap();
+        assert_eq!(log_filters.filters.len(), 2);
+        let mut expected_1 = tst_utils::_simple_filter_from_string("a b c d e");
+        expected_1 = tst_utils::_add_word_alternative(expected_1, 4, "f");
+        let mut expected_2 = tst_utils::_simple_filter_from_string("a c d");
+        expected_2 = tst_utils::_add_word_alternative(expected_2, 0, "b");
+        expected_2 = tst_utils::_add_word_alternative(expected_2, 2, "e");
+        expected_2 = tst_utils::_add_word_alternative(expected_2, 2, "g");
+        assert_eq!(log_filters.filters[0].columns, expected_1);
+        assert_eq!(log_filters.filters[1].columns, expected_2);
         assert_eq!(
-            log_filters.words_hash.get(&"a".to_string()).unwrap(),
-            &vec![0 as usize]
+            log_filters.words_hash.get("a").unwrap(),
+            &vec![0_usize, 1_usize]
         );
         assert_eq!(
-            log_filters.words_hash.get(&"b".to_string()).unwrap(),
-            &vec![0 as usize]
+            log_filters.words_hash.get("b").unwrap(),
+            &vec![0_usize, 1_usize]
         );
         assert_eq!(
-            log_filters.words_hash.get(&"c".to_string()).unwrap(),
-            &vec![0 as usize]
+            log_filters.words_hash.get("c").unwrap(),
+            &vec![0_usize, 1_usize]
         );
         assert_eq!(
-            log_filters.words_hash.get(&"d".to_string()).unwrap(),
-            &vec![0 as usize]
+            log_filters.words_hash.get("d").unwrap(),
+            &vec![0_usize, 1_usize]
         );
         assert_eq!(
-            log_filters.words_hash.get(&"e".to_string()).unwrap(),
-            &vec![0 as usize]
+            log_filters.words_hash.get("e").unwrap(),
+            &vec![0_usize, 1_usize]
         );
+        assert_eq!(log_filters.words_hash.get("f").unwrap(), &vec![0_usize]);
+        assert_eq!(log_filters.words_hash.get("g").unwrap(), &vec![1_usize]);
+    }
 
-        // Filter with alternatives
-        let log_filters_lines = vec!["[a,b],[c],[d,e]"];
+    #[test]
+    fn match_line() {
+        let log_filters = LogFilters::new();
+        assert_eq!(log_filters.match_line("aaa bbb ccc ddd"), None);
+
+        let mut log_filters = tst_utils::_init_test_data();
+        log_filters.ignore_first_columns = 0;
+        log_filters.max_allowed_new_alternatives = 0;
+        // Full match
+        let line_match = log_filters.match_line("aaa bbb ccc ddd").unwrap();
+        assert_eq!(line_match.filter_index, 0);
+        assert_eq!(line_match.score, 4);
+        assert_eq!(
+            line_match.words,
+            tst_utils::_words_vector_from_string("aaa bbb ccc ddd")
+        );
+        assert_eq!(
+            line_match.matched_columns,
+            vec![(0, 0), (1, 1), (2, 2), (3, 3)]
+        );
+        assert!(line_match.new_alternatives.is_empty());
+        // New alternative not allowed
+        assert_eq!(log_filters.match_line("aaa xxx ccc ddd"), None);
+        // New alternative allowed
+        log_filters.max_allowed_new_alternatives = 1;
+        let line_match = log_filters.match_line("iii jjj xxx lll").unwrap();
+        assert_eq!(line_match.filter_index, 2);
+        assert_eq!(line_match.score, 3);
+        assert_eq!(line_match.matched_columns, vec![(0, 0), (1, 1), (3, 3)]);
+        assert_eq!(line_match.new_alternatives, vec![2]);
+        // Ignored columns are not part of matched words
+        log_filters.ignore_first_columns = 1;
+        let line_match = log_filters.match_line("xxx eee fff ggg hhh x y z").unwrap();
+        assert_eq!(line_match.filter_index, 1);
+        assert_eq!(line_match.score, 7);
+        assert_eq!(line_match.words.len(), 7);
+    }
+
+    #[test]
+    fn learn_line() {
         let mut log_filters = LogFilters::new();
+        log_filters.ignore_first_columns = 0;
         log_filters.max_allowed_new_alternatives = 1;
-        log_filters.ignore_numeric_words = true;
-        log_filters.ignore_first_columns = 2;
-        log_filters.from_str_lines(&log_filters_lines);
-        assert_eq!(log_filters.filters.len(), 1);
-        let mut expected = tst_utils::_simple_filter_from_string("a c d");
-        expected = tst_utils::_add_word_alternative(expected, 0, "b");
-        expected = tst_utils::_add_word_alternative(expected, 2, "e");
-        assert_eq!(log_filters.filters[0], expected);
+        assert_eq!(log_filters.learn_line(""), LearnOutcome::Skipped);
+        assert_eq!(
+            log_filters.learn_line("aaa bbb ccc"),
+            LearnOutcome::NewFilter { id: 0 }
+        );
+        assert_eq!(
+            log_filters.learn_line("aaa bbb ccc"),
+            LearnOutcome::Unchanged { id: 0 }
+        );
+        assert_eq!(
+            log_filters.learn_line("aaa xxx ccc"),
+            LearnOutcome::Extended {
+                id: 0,
+                added_alternatives: vec![(1, "xxx".to_string())],
+                new_optional_columns: vec![],
+            }
+        );
         assert_eq!(
-            log_filters.words_hash.get(&"a".to_string()).unwrap(),
-            &vec![0 as usize]
+            log_filters.learn_line("aaa xxx ccc ddd"),
+            LearnOutcome::Extended {
+                id: 0,
+                added_alternatives: vec![(3, "ddd".to_string())],
+                new_optional_columns: vec![3],
+            }
+        );
+        assert_eq!(
+            log_filters.learn_line("foo aaa bbb ccc"),
+            LearnOutcome::Extended {
+                id: 0,
+                added_alternatives: vec![(0, "foo".to_string())],
+                new_optional_columns: vec![0],
+            }
         );
         assert_eq!(
-            log_filters.words_hash.get(&"b".to_string()).unwrap(),
-            &vec![0 as usize]
+            log_filters.learn_line("foo aaa ccc"),
+            LearnOutcome::Extended {
+                id: 0,
+                added_alternatives: vec![],
+                new_optional_columns: vec![2],
+            }
         );
         assert_eq!(
-            log_filters.words_hash.get(&"c".to_string()).unwrap(),
-            &vec![0 as usize]
+            log_filters.to_string(),
+            "[foo,.],[aaa],[bbb,xxx,.],[ccc],[ddd,.]"
         );
         assert_eq!(
-            log_filters.words_hash.get(&"d".to_string()).unwrap(),
-            &vec![0 as usize]
+            log_filters.learn_line("xyz qwe"),
+            LearnOutcome::NewFilter { id: 1 }
         );
         assert_eq!(
-            log_filters.words_hash.get(&"e".to_string()).unwrap(),
-            &vec![0 as usize]
+            LearnOutcome::Extended {
+                id: 0,
+                added_alternatives: vec![(1, "xxx".to_string())],
+                new_optional_columns: vec![3],
+            }
+            .to_string(),
+            "extended filter 0; column 1 += xxx; column 3 optional"
         );
+    }
 
-        // Two filters
-        let log_filters_lines = vec!["[a],[b],[c],[d],[e,f]", "[a,b],[c],[d,e,g]"];
+    #[test]
+    fn merge() {
         let mut log_filters = LogFilters::new();
+        log_filters.ignore_first_columns = 0;
         log_filters.max_allowed_new_alternatives = 1;
-        log_filters.ignore_numeric_words = true;
-        log_filters.ignore_first_columns = 2;
-        log_filters.from_str_lines(&log_filters_lines);
-        assert_eq!(log_filters.filters.len(), 2);
-        let mut expected_1 = tst_utils::_simple_filter_from_string("a b c d e");
-        expected_1 = tst_utils::_add_word_alternative(expected_1, 4, "f");
-        let mut expected_2 = tst_utils::_simple_filter_from_string("a c d");
-        expected_2 = tst_utils::_add_word_alternative(expected_2, 0, "b");
-        expected_2 = tst_utils::_add_word_alternative(expected_2, 2, "e");
-        expected_2 = tst_utils::_add_word_alternative(expected_2, 2, "g");
-        assert_eq!(log_filters.filters[0], expected_1);
-        assert_eq!(log_filters.filters[1], expected_2);
+        log_filters.learn_line("aaa bbb ccc");
+        log_filters.learn_line("aaa xxx ccc");
+        log_filters.learn_line("foo bar");
+
+        let mut other = LogFilters::new();
+        other.ignore_first_columns = 0;
+        other.learn_line("aaa bbb ccc ddd");
+        other.learn_line("qwe rty");
+        other.set_column_optional(1, 1, true).unwrap();
+        other.add_alternative(0, 1, "yyy").unwrap();
+
+        assert!(log_filters.merge(&other).is_empty());
         assert_eq!(
-            log_filters.words_hash.get(&"a".to_string()).unwrap(),
-            &vec![0 as usize, 1 as usize]
+            log_filters.to_string(),
+            "[aaa],[bbb,xxx,yyy],[ccc],[ddd,.],\n[foo],[bar],\n[qwe],[rty,.]"
         );
         assert_eq!(
-            log_filters.words_hash.get(&"b".to_string()).unwrap(),
-            &vec![0 as usize, 1 as usize]
+            log_filters.filters().map(|f| f.id()).collect::<Vec<_>>(),
+            vec![0, 1, 2]
         );
-        assert_eq!(
-            log_filters.words_hash.get(&"c".to_string()).unwrap(),
-            &vec![0 as usize, 1 as usize]
+        for word in &["yyy", "ddd", "qwe", "rty"] {
+            assert!(log_filters.words_hash.contains_key(*word));
+        }
+        assert!(log_filters.is_line_known("aaa yyy ccc"));
+        assert!(log_filters.is_line_known("qwe"));
+
+        // Merging again changes nothing
+        let merged = log_filters.to_string();
+        assert!(log_filters.clone().merge(&other).is_empty());
+        let mut again = log_filters.clone();
+        again.merge(&log_filters);
+        assert_eq!(again.to_string(), merged);
+    }
+
+    #[test]
+    fn merge_conflicts() {
+        let mut log_filters = LogFilters::new();
+        log_filters.ignore_first_columns = 1;
+        log_filters.learn_line("Oct aaa bbb");
+
+        // Learned with fewer ignored columns and numeric words kept
+        let mut other = LogFilters::new();
+        other.ignore_first_columns = 0;
+        other.ignore_numeric_words = false;
+        other.learn_line("Nov aaa 123 bbb");
+        let conflicts = log_filters.merge(&other);
+        assert_eq!(
+            conflicts,
+            vec![
+                ConfigConflict {
+                    parameter: "ignore_numeric_words",
+                    loaded: "true".to_string(),
+                    requested: "false".to_string(),
+                    overridden: false,
+                },
+                ConfigConflict {
+                    parameter: "ignore_first_columns",
+                    loaded: "1".to_string(),
+                    requested: "0".to_string(),
+                    overridden: false,
+                },
+            ]
+        );
+        // Numeric word is dropped, leading column is kept as numeric words were treated differently
+        assert_eq!(log_filters.to_string(), "[aaa],[bbb],\n[Nov],[aaa],[bbb]");
+
+        // Same numeric words handling, leading column can be dropped
+        let mut other = LogFilters::new();
+        other.ignore_first_columns = 0;
+        other.learn_line("Dec aaa bbb");
+        assert_eq!(log_filters.merge(&other).len(), 1);
+        assert_eq!(log_filters.to_string(), "[aaa],[bbb],\n[Nov],[aaa],[bbb]");
+
+        // Words holding own delimiters are dropped
+        let mut other = LogFiltersConfig::new()
+            .with_word_delimiters(" |")
+            .with_ignore_first_columns(1)
+            .build()
+            .unwrap();
+        other.learn_line("Jan aaa bbb:ccc");
+        other.learn_line("Jan aaa bbb:ccc ddd");
+        let conflicts = log_filters.merge(&other);
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(|conflict| conflict.to_string())
+                .collect::<Vec<_>>(),
+            vec![format!(
+                "`tokenizer` was loaded as punctuation(word_delimiters={:?}) \
+                 but punctuation(word_delimiters=\" |\") was requested, \
+                 using punctuation(word_delimiters={:?})",
+                DEFAULT_WORD_DELIMITERS, DEFAULT_WORD_DELIMITERS
+            )]
         );
         assert_eq!(
-            log_filters.words_hash.get(&"d".to_string()).unwrap(),
-            &vec![0 as usize, 1 as usize]
+            log_filters.to_string(),
+            "[aaa],[bbb],\n[Nov],[aaa],[bbb],\n[aaa],\n[aaa],[ddd]"
+        );
+    }
+
+    #[test]
+    fn diff() {
+        let mut old = LogFilters::new();
+        old.ignore_first_columns = 0;
+        old.learn_line("aaa bbb ccc");
+        old.learn_line("foo bar");
+        old.learn_line("qwe rty");
+        old.learn_line("zzz yyy");
+        assert!(old.diff(&old).is_empty());
+
+        // Re-learned in different order, with more lines
+        let mut new = LogFilters::new();
+        new.ignore_first_columns = 0;
+        new.max_allowed_new_alternatives = 1;
+        new.learn_line("qwe rty");
+        new.learn_line("aaa bbb ccc");
+        new.learn_line("aaa xxx ccc");
+        new.learn_line("aaa xxx ccc ddd");
+        new.learn_line("zzz www");
+        new.learn_line("uuu iii");
+        let changes = old.diff(&new);
+        assert_eq!(
+            changes.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+            vec![
+                "removed filter 1",
+                "removed filter 3",
+                "changed filter 1 (was 0); column 1 += xxx; column 3 += ddd; column 3 optional",
+                "added filter 2",
+                "added filter 3",
+            ]
+        );
+        match changes[0] {
+            FilterChange::Removed(ref filter) => assert_eq!(filter, old.filter(1).unwrap()),
+            _ => panic!("Removed expected"),
+        }
+
+        // Matched by identifier, alternative replaced
+        let mut edited = old.clone();
+        edited.add_alternative(1, 1, "baz").unwrap();
+        edited.remove_alternative(1, 1, "bar").unwrap();
+        edited.set_column_optional(1, 0, true).unwrap();
+        assert_eq!(
+            old.diff(&edited),
+            vec![FilterChange::Changed {
+                old: old.filter(1).unwrap().clone(),
+                new: edited.filter(1).unwrap().clone(),
+                added_alternatives: vec![(1, "baz".to_string())],
+                removed_alternatives: vec![(1, "bar".to_string())],
+                new_optional_columns: vec![0],
+            }]
         );
+    }
+
+    #[test]
+    fn remove_filter() {
+        let mut log_filters = tst_utils::_init_test_data();
+        match log_filters.remove_filter(100) {
+            Err(LogmapError::UnknownFilter { id }) => assert_eq!(id, 100),
+            _ => panic!("UnknownFilter expected"),
+        }
+        let removed = log_filters.remove_filter(0).unwrap();
+        assert_eq!(removed.id(), 0);
+        assert_eq!(removed.columns()[1].alternatives(), ["qqq", "bbb"]);
+        assert_eq!(log_filters.filters.len(), 5);
+        let ids: Vec<FilterId> = log_filters.filters().map(Filter::id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        // Remaining references moved by one
+        assert_eq!(log_filters.words_hash.get("aaa").unwrap(), &vec![3, 4]);
+        assert_eq!(log_filters.words_hash.get("eee").unwrap(), &vec![0]);
+        assert_eq!(log_filters.words_hash.get("sss").unwrap(), &vec![3]);
+        assert_eq!(log_filters.words_hash.get("ddd").unwrap(), &vec![4]);
+        assert_eq!(log_filters.filter_index(4), Some(3));
+        // Words found only in removed filter are gone
+        log_filters.remove_filter(2).unwrap();
+        assert!(!log_filters.words_hash.contains_key("iii"));
+        assert_eq!(log_filters.words_hash.get("mmm").unwrap(), &vec![1]);
+        // Lines matching removed filter are no longer known, others still are
+        log_filters.ignore_first_columns = 0;
+        assert!(!log_filters.is_line_known("aaa bbb ccc ddd"));
+        assert!(log_filters.is_line_known("ttt aaa uuu bbb ccc ddd vvv"));
+        // New filters do not reuse identifiers
         assert_eq!(
-            log_filters.words_hash.get(&"e".to_string()).unwrap(),
-            &vec![0 as usize, 1 as usize]
+            log_filters.learn_line("xyz"),
+            LearnOutcome::NewFilter { id: 6 }
         );
+    }
+
+    #[test]
+    fn add_alternative() {
+        let mut log_filters = tst_utils::_init_test_data();
+        log_filters.ignore_first_columns = 0;
+        assert!(!log_filters.is_line_known("iii jjj xyz lll"));
+        assert!(log_filters.add_alternative(2, 2, "xyz").unwrap());
         assert_eq!(
-            log_filters.words_hash.get(&"f".to_string()).unwrap(),
-            &vec![0 as usize]
+            log_filters.filters[2].columns[2].alternatives,
+            ["kkk", "xyz"]
         );
+        assert_eq!(log_filters.words_hash.get("xyz").unwrap(), &vec![2]);
+        assert!(log_filters.is_line_known("iii jjj xyz lll"));
+        // Adding existing alternative changes nothing
+        assert!(!log_filters.add_alternative(2, 2, "xyz").unwrap());
         assert_eq!(
-            log_filters.words_hash.get(&"g".to_string()).unwrap(),
-            &vec![1 as usize]
+            log_filters.filters[2].columns[2].alternatives,
+            ["kkk", "xyz"]
         );
+        // Invalid input
+        match log_filters.add_alternative(2, 4, "xyz") {
+            Err(LogmapError::UnknownColumn { id, column }) => assert_eq!((id, column), (2, 4)),
+            _ => panic!("UnknownColumn expected"),
+        }
+        assert!(log_filters.add_alternative(100, 0, "xyz").is_err());
+        for word in &["", ".", "two words", "a:b", "42", "1*#"] {
+            match log_filters.add_alternative(2, 0, word) {
+                Err(LogmapError::InvalidWord { .. }) => {}
+                _ => panic!("InvalidWord expected for {:?}", word),
+            }
+        }
+        // Numeric words are only accepted when they are kept in lines
+        log_filters.ignore_numeric_words = false;
+        assert!(log_filters.add_alternative(2, 0, "42").unwrap());
+        // Masked text never reaches filters, its placeholder does
+        let mut log_filters = LogFiltersConfig::new()
+            .with_masks(&[Mask::Hex])
+            .build()
+            .unwrap();
+        log_filters.learn_line("a b fault at 0x7f");
+        assert!(log_filters.add_alternative(0, 2, "0x8f").is_err());
+        assert!(log_filters.add_alternative(0, 2, "<IP>").unwrap());
     }
 
     #[test]
-    fn is_word_only_numeric() {
+    fn remove_alternative() {
+        let mut log_filters = tst_utils::_init_test_data();
+        log_filters.ignore_first_columns = 0;
+        // Word also found in other column of the same filter stays in hash
+        assert!(log_filters.add_alternative(5, 0, "vvv").unwrap());
+        assert!(log_filters.remove_alternative(5, 0, "vvv").unwrap());
+        assert_eq!(log_filters.words_hash.get("vvv").unwrap(), &vec![5]);
+        // Word found in other filters keeps their references
+        assert!(log_filters.remove_alternative(0, 1, "bbb").unwrap());
+        assert_eq!(log_filters.filters[0].columns[1].alternatives, ["qqq"]);
+        assert_eq!(log_filters.words_hash.get("bbb").unwrap(), &vec![5]);
+        assert!(!log_filters.is_line_known("aaa bbb ccc ddd"));
+        // Word not found
+        assert!(!log_filters.remove_alternative(0, 1, "bbb").unwrap());
+        assert!(!log_filters.remove_alternative(0, 1, ".").unwrap());
+        // Removing last alternative removes the column
+        assert!(log_filters.remove_alternative(3, 1, "nnn").unwrap());
+        assert_eq!(
+            log_filters.to_string().split(",\n").nth(3).unwrap(),
+            "[mmm],[ooo],[ppp]"
+        );
+        assert!(!log_filters.words_hash.contains_key("nnn"));
+        // Column with only optional marker left is removed as well
+        log_filters.set_column_optional(3, 0, true).unwrap();
+        assert!(log_filters.remove_alternative(3, 0, "mmm").unwrap());
+        assert_eq!(
+            log_filters.filters[3].columns,
+            tst_utils::_simple_filter_from_string("ooo ppp")
+        );
+        // Removing last column removes the filter
+        assert!(log_filters.remove_alternative(3, 0, "ooo").unwrap());
+        assert!(log_filters.remove_alternative(3, 0, "ppp").unwrap());
+        assert_eq!(log_filters.filter_index(3), None);
+        assert_eq!(log_filters.filters.len(), 5);
+        assert_eq!(log_filters.words_hash.get("ttt").unwrap(), &vec![3, 4]);
+    }
+
+    #[test]
+    fn set_column_optional() {
+        let mut log_filters = tst_utils::_init_test_data();
+        log_filters.ignore_first_columns = 0;
+        assert!(!log_filters.is_line_known("iii kkk lll"));
+        log_filters.set_column_optional(2, 1, true).unwrap();
+        log_filters.set_column_optional(2, 1, true).unwrap();
+        assert_eq!(log_filters.filters[2].columns[1].alternatives, ["jjj"]);
+        assert!(log_filters.filters[2].columns[1].optional);
+        assert!(log_filters.is_line_known("iii kkk lll"));
+        log_filters.set_column_optional(2, 1, false).unwrap();
+        assert!(!log_filters.filters[2].columns[1].optional);
+        assert!(!log_filters.is_line_known("iii kkk lll"));
+        assert!(log_filters.set_column_optional(2, 4, true).is_err());
+    }
+
+    #[test]
+    fn filters() {
         let log_filters = LogFilters::new();
-        assert_eq!(log_filters.is_word_only_numeric(&"asdf".to_string()), false);
-        assert_eq!(log_filters.is_word_only_numeric(&"123a".to_string()), false);
-        assert_eq!(log_filters.is_word_only_numeric(&"a123".to_string()), false);
-        assert_eq!(log_filters.is_word_only_numeric(&"6789".to_string()), true);
-        assert_eq!(log_filters.is_word_only_numeric(&"*6789".to_string()), true);
-        assert_eq!(log_filters.is_word_only_numeric(&"#6789".to_string()), true);
+        assert!(log_filters.is_empty());
+        assert_eq!(log_filters.filters().count(), 0);
+
+        let input = "1\n.\nfalse\n0\n3:[aaa],[bbb,ccc,.],\n5:[.,ddd]";
+        let log_filters = LogFilters::load_from(input.as_bytes()).unwrap();
+        assert_eq!(log_filters.len(), 2);
+        let ids: Vec<FilterId> = log_filters.filters().map(Filter::id).collect();
+        assert_eq!(ids, vec![3, 5]);
+        // Optional marker is never exposed as word alternative
+        let filter = log_filters.filter(3).unwrap();
+        assert_eq!(filter.columns().len(), 2);
+        assert_eq!(filter.columns()[0].alternatives(), ["aaa"]);
+        assert!(!filter.columns()[0].is_optional());
+        assert_eq!(filter.columns()[1].alternatives(), ["bbb", "ccc"]);
+        assert!(filter.columns()[1].is_optional());
+        let filter = log_filters.filter(5).unwrap();
+        assert_eq!(filter.columns()[0].alternatives(), ["ddd"]);
+        assert!(filter.columns()[0].is_optional());
+        assert!(log_filters.filter(4).is_none());
+        assert_eq!(log_filters.to_string(), "[aaa],[bbb,ccc,.],\n[ddd,.]");
+        // Column holding only the marker has no words to match
+        assert!(LogFilters::load_from("1\n.\nfalse\n0\n[aaa],[.]".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn compile() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CompiledFilters>();
+
+        let mut log_filters = tst_utils::_init_test_data();
+        log_filters.ignore_first_columns = 0;
+        let compiled = log_filters.compile();
+        assert_eq!(compiled.len(), 6);
+        assert_eq!(compiled.config(), log_filters.config());
+        assert!(compiled.is_line_known("aaa bbb ccc ddd"));
+        assert_eq!(
+            compiled
+                .match_line("eee fff ggg hhh x y z")
+                .unwrap()
+                .filter_id,
+            1
+        );
+
+        // Snapshot does not follow further learning
+        log_filters.learn_line("xyz qwe asd");
+        assert!(log_filters.is_line_known("xyz qwe asd"));
+        assert!(!compiled.is_line_known("xyz qwe asd"));
+        assert_eq!(compiled.len(), 6);
+
+        // Snapshot can be shared between threads
+        let compiled = Arc::new(CompiledFilters::from(log_filters));
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let compiled = Arc::clone(&compiled);
+                thread::spawn(move || {
+                    compiled.is_line_known("xyz qwe asd") && !compiled.is_line_known("foo bar")
+                })
+            })
+            .collect();
+        for reader in readers {
+            assert!(reader.join().unwrap());
+        }
+
+        // Lines are matched the same way as by `LogFilters`
+        let mut log_filters = tst_utils::_init_test_data();
+        log_filters.ignore_first_columns = 0;
+        log_filters.max_allowed_new_alternatives = 1;
+        log_filters.set_column_optional(2, 1, true).unwrap();
+        log_filters.add_rule(Rule::regex("^usb [0-9]+ ").unwrap());
+        log_filters.add_rule(Rule::template("<*> qwe <**>").unwrap());
+        let compiled = log_filters.compile();
+        for line in &[
+            "aaa bbb ccc ddd",
+            "aaa qqq rrr sss",
+            "aaa xxx rrr sss",
+            "aaa rrr sss",
+            "qqq rrr sss aaa",
+            "ttt aaa uuu bbb ccc ddd vvv",
+            "ttt aaa bbb ccc ddd",
+            "iii kkk lll",
+            "iii jjj jjj kkk lll",
+            "eee fff ggg hhh x y z zz",
+            "aaa aaa aaa aaa",
+            "usb 12 connected",
+            "xyz qwe",
+            "",
+        ] {
+            assert_eq!(
+                compiled.match_line(line),
+                log_filters.match_line(line),
+                "{:?}",
+                line
+            );
+            assert_eq!(
+                compiled.is_line_known(line),
+                log_filters.is_line_known(line),
+                "{:?}",
+                line
+            );
+        }
+        assert_eq!(compiled.filter(2), log_filters.filter(2));
+        assert!(compiled.filter(100).is_none());
+        assert_eq!(compiled.rules().count(), 2);
+    }
+
+    #[test]
+    fn template_matches() {
+        let matches = |template: &str, line: &str| {
+            Rule::template_matches(
+                &tst_utils::_words_vector_from_string(template),
+                &tst_utils::_words_vector_from_string(line),
+            )
+        };
+        assert!(matches("aaa bbb", "aaa bbb"));
+        assert!(!matches("aaa bbb", "aaa bbb ccc"));
+        assert!(!matches("aaa bbb ccc", "aaa bbb"));
+        assert!(matches("aaa <*> ccc", "aaa bbb ccc"));
+        assert!(!matches("aaa <*> ccc", "aaa ccc"));
+        assert!(!matches("aaa <*> ccc", "aaa bbb bbb ccc"));
+        assert!(matches("aaa <**> ccc", "aaa ccc"));
+        assert!(matches("aaa <**> ccc", "aaa bbb ccc ccc"));
+        assert!(!matches("aaa <**> ccc", "aaa bbb ccc ddd"));
+        assert!(matches("<**> ccc <*>", "aaa ccc bbb ccc ddd"));
+        assert!(matches("aaa <**>", "aaa"));
+        assert!(matches("<**>", ""));
+        assert!(!matches("<*>", ""));
+    }
+
+    #[test]
+    fn rules() {
+        let mut log_filters = tst_utils::_init_test_data();
+        assert!(!log_filters.is_line_known("Oct 12 host CRON 42 session opened for user root"));
+
+        let rule = Rule::template("CRON <**> opened for user <*>").unwrap();
+        assert!(format!("{:?}", rule).contains("Template(None)"));
+        log_filters.add_rule(rule);
+        match log_filters.rules().next().unwrap().kind {
+            RuleKind::Template(Some(ref template)) => {
+                assert_eq!(template, &["CRON", "<**>", "opened", "for", "user", "<*>"])
+            }
+            _ => panic!("split template expected"),
+        }
+        assert!(log_filters.is_line_known("Oct 12 host CRON 42 session opened for user root"));
+        // First columns and numeric words are dropped before matching templates
+        assert!(log_filters.is_line_known("Oct 12 host CRON 42 opened 7 for user root"));
+        assert!(!log_filters.is_line_known("CRON 42 session opened for user root"));
+        assert!(!log_filters.is_line_known("Oct 12 host CRON session opened for user"));
+
+        log_filters.add_rule(Rule::regex(r"^\S+ \S+ kernel: usb \d+-\d+:").unwrap());
+        assert!(log_filters.is_line_known("Oct 12 kernel: usb 1-2: new device"));
+        assert!(!log_filters.is_line_known("Oct 12 kernel: usb 1-x: new device"));
+
+        // Learned filters still match and learning does not change rules
+        log_filters.ignore_first_columns = 0;
+        assert!(log_filters.is_line_known("aaa bbb ccc ddd"));
+        log_filters.learn_line("xyz qwe asd");
+        assert_eq!(log_filters.rules().count(), 2);
+        assert!(log_filters
+            .compile()
+            .is_line_known("Oct 12 kernel: usb 1-2: new device"));
+
+        assert_eq!(
+            Rule::template("  ").unwrap_err().to_string(),
+            "rule \"  \" is invalid: template has no words"
+        );
+        assert!(Rule::regex("usb (").is_err());
+    }
+
+    #[test]
+    fn load_rules_from() {
+        let mut log_filters = LogFilters::new();
+        log_filters
+            .load_rules_from(
+                "# rules kept by hand\n\
+                 template: CRON <**> session opened for user <*>\n\
+                 \n\
+                 regex:^\\S+ \\S+ kernel: usb \\d+-\\d+:\n"
+                    .as_bytes(),
+            )
+            .unwrap();
+        let rules: Vec<String> = log_filters.rules().map(|rule| rule.to_string()).collect();
+        assert_eq!(
+            rules,
+            [
+                "template: CRON <**> session opened for user <*>",
+                "regex: ^\\S+ \\S+ kernel: usb \\d+-\\d+:",
+            ]
+        );
+        assert_eq!(
+            log_filters.rules().next().unwrap().pattern(),
+            "CRON <**> session opened for user <*>"
+        );
+
+        for (rules, line) in &[
+            ("regex: ok\nglob: CRON <*>\n", 2),
+            ("# comment\n\nregex: usb (\n", 3),
+            ("template:  \n", 1),
+            ("template\n", 1),
+        ] {
+            match log_filters.load_rules_from(rules.as_bytes()) {
+                Err(LogmapError::BadRuleLine {
+                    path: None,
+                    line: found,
+                    ..
+                }) => assert_eq!(found, *line, "{:?}", rules),
+                other => panic!("unexpected result for {:?}: {:?}", rules, other),
+            }
+        }
+        // Malformed files add no rules
+        assert_eq!(log_filters.rules().count(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let mut log_filters = tst_utils::_init_test_data();
+        log_filters.ignore_first_columns = 0;
+        log_filters.set_column_optional(2, 1, true).unwrap();
+        let json = serde_json::to_string(&log_filters).unwrap();
+        assert!(!json.contains("words_hash"));
+
+        // Index of words is rebuilt
+        let mut deserialized: LogFilters = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.filters, log_filters.filters);
+        assert_eq!(deserialized.words_hash, log_filters.words_hash);
+        assert_eq!(deserialized.config(), log_filters.config());
+        assert!(deserialized.is_line_known("iii kkk lll"));
+        assert_eq!(
+            deserialized.learn_line("xyz"),
+            LearnOutcome::NewFilter { id: 6 }
+        );
+
+        // Filters are validated
+        let json = json.replace("\"fff\"", "\"f f\"");
+        match serde_json::from_str::<LogFilters>(&json) {
+            Err(err) => assert!(err.to_string().contains("filter 1 is invalid")),
+            Ok(_) => panic!("Invalid word alternative accepted"),
+        }
+        let json = r#"{"filters":[{"id":1,"columns":[{"alternatives":["a"],"optional":false}]},
+            {"id":1,"columns":[{"alternatives":["b"],"optional":true}]}],
+            "next_filter_id":0,"max_allowed_new_alternatives":0,"denote_optional":".",
+            "ignore_numeric_words":true,"ignore_first_columns":2}"#;
+        assert!(serde_json::from_str::<LogFilters>(json).is_err());
+        let json = json.replace(
+            "\"id\":1,\"columns\":[{\"alternatives\":[\"b\"]",
+            "\"id\":0,\"columns\":[{\"alternatives\":[\"b\"]",
+        );
+        let log_filters: LogFilters = serde_json::from_str(&json).unwrap();
+        assert_eq!(log_filters.next_filter_id, 2);
+        assert!(serde_json::from_str::<LogFilters>(&json.replace("\".\"", "\"[\"")).is_err());
+
+        // Unset configuration values stay unset
+        let config = LogFiltersConfig::new().with_ignore_first_columns(0);
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<LogFiltersConfig>(&json).unwrap(),
+            config
+        );
+        assert_eq!(
+            serde_json::from_str::<LogFiltersConfig>(r#"{"ignore_numeric_words":false}"#).unwrap(),
+            LogFiltersConfig::new().with_ignore_numeric_words(false)
+        );
+
+        // Tokenizer is stored as its settings
+        let config = LogFiltersConfig::new().with_tokenizer(RegexTokenizer::new("[ =]+").unwrap());
+        let json = serde_json::to_string(&config).unwrap();
         assert_eq!(
-            log_filters.is_word_only_numeric(&"6789*6789".to_string()),
-            true
+            json,
+            r#"{"max_allowed_new_alternatives":null,"denote_optional":null,"ignore_numeric_words":null,"ignore_first_columns":null,"tokenizer":{"kind":"regex","pattern":"[ =]+"},"masks":null}"#
         );
         assert_eq!(
-            log_filters.is_word_only_numeric(&"6789#6789".to_string()),
-            true
+            serde_json::from_str::<LogFiltersConfig>(&json).unwrap(),
+            config
         );
-        assert_eq!(log_filters.is_word_only_numeric(&"".to_string()), true);
+        assert!(
+            serde_json::from_str::<LogFiltersConfig>(r#"{"tokenizer":{"kind":"custom"}}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn is_word_only_numeric() {
+        assert!(!super::is_word_only_numeric("asdf"));
+        assert!(!super::is_word_only_numeric("123a"));
+        assert!(!super::is_word_only_numeric("a123"));
+        assert!(super::is_word_only_numeric("6789"));
+        assert!(super::is_word_only_numeric("*6789"));
+        assert!(super::is_word_only_numeric("#6789"));
+        assert!(super::is_word_only_numeric("6789*6789"));
+        assert!(super::is_word_only_numeric("6789#6789"));
+        assert!(super::is_word_only_numeric(""));
     }
 
     #[test]
@@ -1102,10 +6047,10 @@ mod tests {
         log_filters.max_allowed_new_alternatives = 0;
         let mut complex_filter =
             tst_utils::_simple_filter_from_string("eee fff ggg hhh iii jjj kkk lll");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 4, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 5, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 6, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 7, ".");
+        complex_filter = tst_utils::_set_optional(complex_filter, 4);
+        complex_filter = tst_utils::_set_optional(complex_filter, 5);
+        complex_filter = tst_utils::_set_optional(complex_filter, 6);
+        complex_filter = tst_utils::_set_optional(complex_filter, 7);
         tst_utils::_add_test_filter(&mut log_filters, complex_filter);
         let words = tst_utils::_words_vector_from_string("eee fff ggg hhh");
         assert_eq!(log_filters.find_best_matching_filter_index(&words), 0);
@@ -1114,14 +6059,14 @@ mod tests {
         log_filters.max_allowed_new_alternatives = 0;
         let mut complex_filter =
             tst_utils::_simple_filter_from_string("eee fff ggg hhh iii jjj kkk lll");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 0, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 1, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 2, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 3, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 4, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 5, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 6, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 7, ".");
+        complex_filter = tst_utils::_set_optional(complex_filter, 0);
+        complex_filter = tst_utils::_set_optional(complex_filter, 1);
+        complex_filter = tst_utils::_set_optional(complex_filter, 2);
+        complex_filter = tst_utils::_set_optional(complex_filter, 3);
+        complex_filter = tst_utils::_set_optional(complex_filter, 4);
+        complex_filter = tst_utils::_set_optional(complex_filter, 5);
+        complex_filter = tst_utils::_set_optional(complex_filter, 6);
+        complex_filter = tst_utils::_set_optional(complex_filter, 7);
         tst_utils::_add_test_filter(&mut log_filters, complex_filter);
         let words = tst_utils::_words_vector_from_string("mmm nnn ooo ppp");
         assert_eq!(log_filters.find_best_matching_filter_index(&words), -1);
@@ -1134,20 +6079,20 @@ mod tests {
         // Test what happens if method was used on empty data structure
         let log_filters = LogFilters::new();
         assert_eq!(
-            log_filters.get_filter_indexes_with_min_req_matches(&vec![]),
-            vec![]
+            log_filters.get_filter_indexes_with_min_req_matches(&[]),
+            Vec::<usize>::new()
         );
         let words = tst_utils::_words_vector_from_string("aaa bbb ccc ddd");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
 
         let mut log_filters = tst_utils::_init_test_data();
         log_filters.max_allowed_new_alternatives = 1;
         assert_eq!(
-            log_filters.get_sorted_filter_indexes_containing_words(&vec![]),
-            vec![]
+            log_filters.get_sorted_filter_indexes_containing_words(&[]),
+            Vec::<usize>::new()
         );
         let words = tst_utils::_words_vector_from_string("aaa bbb ccc ddd");
         assert_eq!(
@@ -1159,7 +6104,7 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("aaa bbb");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         log_filters.max_allowed_new_alternatives = 2;
         let words = tst_utils::_words_vector_from_string("aaa bbb");
@@ -1171,13 +6116,13 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("aaa");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         log_filters.max_allowed_new_alternatives = 2;
         let words = tst_utils::_words_vector_from_string("aaa");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         log_filters.max_allowed_new_alternatives = 3;
         let words = tst_utils::_words_vector_from_string("aaa");
@@ -1190,14 +6135,14 @@ mod tests {
         let words = vec![];
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         // One-word words vector will only match if at least one filter contains that word
         log_filters.max_allowed_new_alternatives = 1;
         let words = tst_utils::_words_vector_from_string("xyz");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         // Test when new word alternatives are required
         log_filters.max_allowed_new_alternatives = 1;
@@ -1211,7 +6156,7 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("aaa lll ccc");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         log_filters.max_allowed_new_alternatives = 2;
         let words = tst_utils::_words_vector_from_string("aaa lll ccc");
@@ -1224,7 +6169,7 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("aaa lll zzz ddd");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         log_filters.max_allowed_new_alternatives = 2;
         let words = tst_utils::_words_vector_from_string("aaa lll zzz ddd");
@@ -1236,7 +6181,7 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("aaa lll zzz yyy ddd");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         log_filters.max_allowed_new_alternatives = 3;
         let words = tst_utils::_words_vector_from_string("aaa lll zzz yyy ddd");
@@ -1249,13 +6194,13 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("ddd lll zzz yyy aaa");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         log_filters.max_allowed_new_alternatives = 2;
         let words = tst_utils::_words_vector_from_string("ddd lll zzz yyy aaa");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         log_filters.max_allowed_new_alternatives = 3;
         let words = tst_utils::_words_vector_from_string("ddd lll zzz yyy aaa");
@@ -1268,10 +6213,10 @@ mod tests {
         log_filters.max_allowed_new_alternatives = 0;
         let mut complex_filter =
             tst_utils::_simple_filter_from_string("eee fff ggg hhh iii jjj kkk lll");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 4, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 5, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 6, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 7, ".");
+        complex_filter = tst_utils::_set_optional(complex_filter, 4);
+        complex_filter = tst_utils::_set_optional(complex_filter, 5);
+        complex_filter = tst_utils::_set_optional(complex_filter, 6);
+        complex_filter = tst_utils::_set_optional(complex_filter, 7);
         tst_utils::_add_test_filter(&mut log_filters, complex_filter);
         let words = tst_utils::_words_vector_from_string("eee fff ggg hhh");
         assert_eq!(
@@ -1283,19 +6228,19 @@ mod tests {
         log_filters.max_allowed_new_alternatives = 0;
         let mut complex_filter =
             tst_utils::_simple_filter_from_string("eee fff ggg hhh iii jjj kkk lll");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 0, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 1, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 2, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 3, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 4, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 5, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 6, ".");
-        complex_filter = tst_utils::_add_word_alternative(complex_filter, 7, ".");
+        complex_filter = tst_utils::_set_optional(complex_filter, 0);
+        complex_filter = tst_utils::_set_optional(complex_filter, 1);
+        complex_filter = tst_utils::_set_optional(complex_filter, 2);
+        complex_filter = tst_utils::_set_optional(complex_filter, 3);
+        complex_filter = tst_utils::_set_optional(complex_filter, 4);
+        complex_filter = tst_utils::_set_optional(complex_filter, 5);
+        complex_filter = tst_utils::_set_optional(complex_filter, 6);
+        complex_filter = tst_utils::_set_optional(complex_filter, 7);
         tst_utils::_add_test_filter(&mut log_filters, complex_filter);
         let words = tst_utils::_words_vector_from_string("mmm nnn ooo ppp");
         assert_eq!(
             log_filters.get_filter_indexes_with_min_req_matches(&words),
-            vec![]
+            Vec::<usize>::new()
         );
 
         // TODO: more unit-tests to cover edge cases for max_allowed_new_alternatives
@@ -1307,18 +6252,18 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("aaa bbb ccc ddd");
         assert_eq!(
             log_filters.get_sorted_filter_indexes_containing_words(&words),
-            vec![]
+            Vec::<usize>::new()
         );
         assert_eq!(
-            log_filters.get_sorted_filter_indexes_containing_words(&vec![]),
-            vec![]
+            log_filters.get_sorted_filter_indexes_containing_words(&[]),
+            Vec::<usize>::new()
         );
 
         let mut log_filters = tst_utils::_init_test_data();
         log_filters.max_allowed_new_alternatives = 1;
         assert_eq!(
-            log_filters.get_sorted_filter_indexes_containing_words(&vec![]),
-            vec![]
+            log_filters.get_sorted_filter_indexes_containing_words(&[]),
+            Vec::<usize>::new()
         );
         let words = tst_utils::_words_vector_from_string("aaa bbb ccc ddd");
         assert_eq!(
@@ -1333,7 +6278,7 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("xxx");
         assert_eq!(
             log_filters.get_sorted_filter_indexes_containing_words(&words),
-            vec![]
+            Vec::<usize>::new()
         );
     }
 
@@ -1344,11 +6289,11 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("aaa bbb ccc ddd");
         assert_eq!(log_filters.count_consequent_matches(&words, 0), 0);
         assert_eq!(log_filters.count_consequent_matches(&words, 1), 0);
-        assert_eq!(log_filters.count_consequent_matches(&vec![], 0), 0);
+        assert_eq!(log_filters.count_consequent_matches(&[], 0), 0);
         log_filters.max_allowed_new_alternatives = 0;
         assert_eq!(log_filters.count_consequent_matches(&words, 0), 0);
         assert_eq!(log_filters.count_consequent_matches(&words, 1), 0);
-        assert_eq!(log_filters.count_consequent_matches(&vec![], 0), 0);
+        assert_eq!(log_filters.count_consequent_matches(&[], 0), 0);
 
         let mut log_filters = tst_utils::_init_test_data();
         log_filters.max_allowed_new_alternatives = 1;
@@ -1362,7 +6307,7 @@ mod tests {
             0
         );
         // Test empty words vector
-        assert_eq!(log_filters.count_consequent_matches(&vec![], 0), 0);
+        assert_eq!(log_filters.count_consequent_matches(&[], 0), 0);
         // Test if words vector can be smaller than filter
         let words = tst_utils::_words_vector_from_string("iii jjj lll");
         assert_eq!(log_filters.count_consequent_matches(&words, 2), 3);
@@ -1406,62 +6351,26 @@ mod tests {
     fn get_word_index_in_filter() {
         // Test what happens if method was used on empty data structure
         let log_filters = LogFilters::new();
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"aaa".to_string(), 0, 0),
-            -1
-        );
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"aaa".to_string(), 0, 100),
-            -1
-        );
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"aaa".to_string(), 100, 0),
-            -1
-        );
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"".to_string(), 0, 0),
-            -1
-        );
+        assert_eq!(log_filters.get_word_index_in_filter("aaa", 0, 0), -1);
+        assert_eq!(log_filters.get_word_index_in_filter("aaa", 0, 100), -1);
+        assert_eq!(log_filters.get_word_index_in_filter("aaa", 100, 0), -1);
+        assert_eq!(log_filters.get_word_index_in_filter("", 0, 0), -1);
 
         let log_filters = tst_utils::_init_test_data();
         // Test if word will be matched when it should be
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"aaa".to_string(), 0, 0),
-            0
-        );
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"aaa".to_string(), 4, 0),
-            3
-        );
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"qqq".to_string(), 0, 0),
-            1
-        );
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"sss".to_string(), 0, 3),
-            3
-        );
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"ddd".to_string(), 0, 3),
-            3
-        );
+        assert_eq!(log_filters.get_word_index_in_filter("aaa", 0, 0), 0);
+        assert_eq!(log_filters.get_word_index_in_filter("aaa", 4, 0), 3);
+        assert_eq!(log_filters.get_word_index_in_filter("qqq", 0, 0), 1);
+        assert_eq!(log_filters.get_word_index_in_filter("sss", 0, 3), 3);
+        assert_eq!(log_filters.get_word_index_in_filter("ddd", 0, 3), 3);
         // Test if word will not be matched if starting index is higher than word index in filter
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"aaa".to_string(), 0, 1),
-            -1
-        );
+        assert_eq!(log_filters.get_word_index_in_filter("aaa", 0, 1), -1);
         // Empty string should not be matched
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"".to_string(), 4, 0),
-            -1
-        );
+        assert_eq!(log_filters.get_word_index_in_filter("", 4, 0), -1);
         // Test when word does not exist in filter or filter does not exist
+        assert_eq!(log_filters.get_word_index_in_filter("aaa", 1, 0), -1);
         assert_eq!(
-            log_filters.get_word_index_in_filter(&"aaa".to_string(), 1, 0),
-            -1
-        );
-        assert_eq!(
-            log_filters.get_word_index_in_filter(&"aaa".to_string(), log_filters.filters.len(), 0),
+            log_filters.get_word_index_in_filter("aaa", log_filters.filters.len(), 0),
             -1
         );
     }
@@ -1470,16 +6379,16 @@ mod tests {
     fn update_filter() {
         // Test empty data structure
         let mut log_filters = LogFilters::new();
-        log_filters.update_filter(&vec![], 0);
+        log_filters.update_filter(&[], 0);
         assert_eq!(log_filters.filters.len(), 0);
 
         let mut log_filters = tst_utils::_init_test_data();
         log_filters.max_allowed_new_alternatives = 1;
         log_filters.denote_optional = ".".to_string();
         // Try to update based on empty words vector
-        let filter_0_len = log_filters.filters[0].len();
-        log_filters.update_filter(&vec![], 0);
-        assert_eq!(log_filters.filters[0].len(), filter_0_len);
+        let filter_0_len = log_filters.filters[0].columns.len();
+        log_filters.update_filter(&[], 0);
+        assert_eq!(log_filters.filters[0].columns.len(), filter_0_len);
         // Try to update a filter that does not exist
         let words = tst_utils::_words_vector_from_string("aaa bbb ccc xxx");
         let nonexisting_filter_index = log_filters.filters.len();
@@ -1488,57 +6397,48 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("mmm nnn ooo ppp");
         log_filters.update_filter(&words, 3);
         let expected = tst_utils::_simple_filter_from_string("mmm nnn ooo ppp");
-        assert_eq!(log_filters.filters.get(3).unwrap(), &expected);
+        assert_eq!(log_filters.filters[3].columns, expected);
 
         // One new (hence optional) word alternative added at the front of filter
         let words = tst_utils::_words_vector_from_string("foo qqq rrr sss ttt");
         log_filters.update_filter(&words, 4);
         let mut expected = tst_utils::_simple_filter_from_string("foo qqq rrr sss ttt");
-        expected = tst_utils::_add_word_alternative(expected, 0, ".");
+        expected = tst_utils::_set_optional(expected, 0);
         expected = tst_utils::_add_word_alternative(expected, 4, "aaa");
-        assert_eq!(log_filters.filters.get(4).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"foo".to_string()).unwrap(),
-            &vec![4]
-        );
+        assert_eq!(log_filters.filters[4].columns, expected);
+        assert_eq!(log_filters.words_hash.get("foo").unwrap(), &vec![4]);
         // Two new (hence optional) word alternatives added at the front of filter
         let words = tst_utils::_words_vector_from_string("xyz qwe mmm nnn ooo ppp");
         log_filters.update_filter(&words, 3);
         let mut expected = tst_utils::_simple_filter_from_string("xyz qwe mmm nnn ooo ppp");
-        expected = tst_utils::_add_word_alternative(expected, 0, ".");
-        expected = tst_utils::_add_word_alternative(expected, 1, ".");
-        assert_eq!(log_filters.filters.get(3).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"xyz".to_string()).unwrap(),
-            &vec![3]
-        );
-        assert_eq!(
-            log_filters.words_hash.get(&"qwe".to_string()).unwrap(),
-            &vec![3]
-        );
+        expected = tst_utils::_set_optional(expected, 0);
+        expected = tst_utils::_set_optional(expected, 1);
+        assert_eq!(log_filters.filters[3].columns, expected);
+        assert_eq!(log_filters.words_hash.get("xyz").unwrap(), &vec![3]);
+        assert_eq!(log_filters.words_hash.get("qwe").unwrap(), &vec![3]);
         // One word turned to (optional) alternative as a result of words vector shorter than filter
         let words = tst_utils::_words_vector_from_string("fff ggg hhh x y z");
         log_filters.update_filter(&words, 1);
         let mut expected = tst_utils::_simple_filter_from_string("eee fff ggg hhh x y z");
-        expected = tst_utils::_add_word_alternative(expected, 0, ".");
-        assert_eq!(log_filters.filters.get(1).unwrap(), &expected);
+        expected = tst_utils::_set_optional(expected, 0);
+        assert_eq!(log_filters.filters[1].columns, expected);
         // Two words turned to (optional) alternatives as a result of words vector shorter than filter
         let words = tst_utils::_words_vector_from_string("kkk lll");
         log_filters.update_filter(&words, 2);
         let mut expected = tst_utils::_simple_filter_from_string("iii jjj kkk lll");
-        expected = tst_utils::_add_word_alternative(expected, 0, ".");
-        expected = tst_utils::_add_word_alternative(expected, 1, ".");
-        assert_eq!(log_filters.filters.get(2).unwrap(), &expected);
+        expected = tst_utils::_set_optional(expected, 0);
+        expected = tst_utils::_set_optional(expected, 1);
+        assert_eq!(log_filters.filters[2].columns, expected);
         // One word turned to optional alternative and one new alternative added
         let words = tst_utils::_words_vector_from_string("bar ccc sss");
         log_filters.update_filter(&words, 0);
         let mut expected = tst_utils::_simple_filter_from_string("aaa qqq ccc sss");
-        expected = tst_utils::_add_word_alternative(expected, 0, ".");
+        expected = tst_utils::_set_optional(expected, 0);
         expected = tst_utils::_add_word_alternative(expected, 1, "bbb");
         expected = tst_utils::_add_word_alternative(expected, 1, "bar");
         expected = tst_utils::_add_word_alternative(expected, 2, "rrr");
         expected = tst_utils::_add_word_alternative(expected, 3, "ddd");
-        assert_eq!(log_filters.filters.get(0).unwrap(), &expected);
+        assert_eq!(log_filters.filters[0].columns, expected);
 
         let mut log_filters = tst_utils::_init_test_data();
         log_filters.max_allowed_new_alternatives = 1;
@@ -1548,41 +6448,26 @@ mod tests {
         log_filters.update_filter(&words, 2);
         let mut expected = tst_utils::_simple_filter_from_string("iii jjj kkk lll");
         expected = tst_utils::_add_word_alternative(expected, 2, "foo");
-        assert_eq!(log_filters.filters.get(2).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"foo".to_string()).unwrap(),
-            &vec![2]
-        );
+        assert_eq!(log_filters.filters[2].columns, expected);
+        assert_eq!(log_filters.words_hash.get("foo").unwrap(), &vec![2]);
         // Add alternatives to consequent two words in the middle
         let words = tst_utils::_words_vector_from_string("ttt aaa xyz qwe ccc ddd vvv");
         log_filters.update_filter(&words, 5);
         let mut expected = tst_utils::_simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv");
         expected = tst_utils::_add_word_alternative(expected, 2, "xyz");
         expected = tst_utils::_add_word_alternative(expected, 3, "qwe");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"xyz".to_string()).unwrap(),
-            &vec![5]
-        );
-        assert_eq!(
-            log_filters.words_hash.get(&"qwe".to_string()).unwrap(),
-            &vec![5]
-        );
+        assert_eq!(log_filters.filters[5].columns, expected);
+        assert_eq!(log_filters.words_hash.get("xyz").unwrap(), &vec![5]);
+        assert_eq!(log_filters.words_hash.get("qwe").unwrap(), &vec![5]);
         // Add alternatives to two non-consequent words in the middle
         let words = tst_utils::_words_vector_from_string("eee fff bar hhh x baz z");
         log_filters.update_filter(&words, 1);
         let mut expected = tst_utils::_simple_filter_from_string("eee fff ggg hhh x y z");
         expected = tst_utils::_add_word_alternative(expected, 2, "bar");
         expected = tst_utils::_add_word_alternative(expected, 5, "baz");
-        assert_eq!(log_filters.filters.get(1).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"bar".to_string()).unwrap(),
-            &vec![1]
-        );
-        assert_eq!(
-            log_filters.words_hash.get(&"baz".to_string()).unwrap(),
-            &vec![1]
-        );
+        assert_eq!(log_filters.filters[1].columns, expected);
+        assert_eq!(log_filters.words_hash.get("bar").unwrap(), &vec![1]);
+        assert_eq!(log_filters.words_hash.get("baz").unwrap(), &vec![1]);
 
         let mut log_filters = tst_utils::_init_test_data();
         log_filters.max_allowed_new_alternatives = 1;
@@ -1591,21 +6476,21 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("ttt aaa bbb ccc ddd vvv");
         log_filters.update_filter(&words, 5);
         let mut expected = tst_utils::_simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv");
-        expected = tst_utils::_add_word_alternative(expected, 2, ".");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
+        expected = tst_utils::_set_optional(expected, 2);
+        assert_eq!(log_filters.filters[5].columns, expected);
         // Turn two non-consequent words in the middle to optional alternatives
         let words = tst_utils::_words_vector_from_string("eee ggg x y z");
         log_filters.update_filter(&words, 1);
         let mut expected = tst_utils::_simple_filter_from_string("eee fff ggg hhh x y z");
-        expected = tst_utils::_add_word_alternative(expected, 1, ".");
-        expected = tst_utils::_add_word_alternative(expected, 3, ".");
-        assert_eq!(log_filters.filters.get(1).unwrap(), &expected);
+        expected = tst_utils::_set_optional(expected, 1);
+        expected = tst_utils::_set_optional(expected, 3);
+        assert_eq!(log_filters.filters[1].columns, expected);
         // Turn one word in the middle to optional alternative
         let words = tst_utils::_words_vector_from_string("iii jjj lll");
         log_filters.update_filter(&words, 2);
         let mut expected = tst_utils::_simple_filter_from_string("iii jjj kkk lll");
-        expected = tst_utils::_add_word_alternative(expected, 2, ".");
-        assert_eq!(log_filters.filters.get(2).unwrap(), &expected);
+        expected = tst_utils::_set_optional(expected, 2);
+        assert_eq!(log_filters.filters[2].columns, expected);
 
         let mut log_filters = tst_utils::_init_test_data();
         log_filters.max_allowed_new_alternatives = 1;
@@ -1615,11 +6500,8 @@ mod tests {
         log_filters.update_filter(&words, 5);
         let mut expected = tst_utils::_simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv");
         expected = tst_utils::_add_word_alternative(expected, 6, "xyz");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"xyz".to_string()).unwrap(),
-            &vec![5]
-        );
+        assert_eq!(log_filters.filters[5].columns, expected);
+        assert_eq!(log_filters.words_hash.get("xyz").unwrap(), &vec![5]);
 
         let mut log_filters = tst_utils::_init_test_data();
         log_filters.max_allowed_new_alternatives = 1;
@@ -1628,8 +6510,8 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("ttt aaa uuu bbb ccc ddd");
         log_filters.update_filter(&words, 5);
         let mut expected = tst_utils::_simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv");
-        expected = tst_utils::_add_word_alternative(expected, 6, ".");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
+        expected = tst_utils::_set_optional(expected, 6);
+        assert_eq!(log_filters.filters[5].columns, expected);
 
         let mut log_filters = tst_utils::_init_test_data();
         log_filters.max_allowed_new_alternatives = 1;
@@ -1638,12 +6520,9 @@ mod tests {
         let words = tst_utils::_words_vector_from_string("ttt aaa uuu bbb ccc ddd vvv xyz");
         log_filters.update_filter(&words, 5);
         let mut expected = tst_utils::_simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv xyz");
-        expected = tst_utils::_add_word_alternative(expected, 7, ".");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"xyz".to_string()).unwrap(),
-            &vec![5]
-        );
+        expected = tst_utils::_set_optional(expected, 7);
+        assert_eq!(log_filters.filters[5].columns, expected);
+        assert_eq!(log_filters.words_hash.get("xyz").unwrap(), &vec![5]);
     }
 
     #[test]
@@ -1651,7 +6530,7 @@ mod tests {
         // Test empty data structure
         let mut log_filters = LogFilters::new();
         assert_eq!(
-            log_filters.normalise_lengths_before_first_match(&vec![], 0, 0, 0),
+            log_filters.normalise_lengths_before_first_match(&[], 0, 0, 0),
             (-1, -1)
         );
         assert_eq!(log_filters.filters.len(), 0);
@@ -1668,12 +6547,12 @@ mod tests {
         log_filters.max_allowed_new_alternatives = 1;
         log_filters.denote_optional = ".".to_string();
         // Try to update based on empty words vector
-        let filter_0_len = log_filters.filters[0].len();
+        let filter_0_len = log_filters.filters[0].columns.len();
         assert_eq!(
-            log_filters.normalise_lengths_before_first_match(&vec![], 0, 0, 0),
+            log_filters.normalise_lengths_before_first_match(&[], 0, 0, 0),
             (-1, -1)
         );
-        assert_eq!(log_filters.filters[0].len(), filter_0_len);
+        assert_eq!(log_filters.filters[0].columns.len(), filter_0_len);
         // Try to update a filter that does not exist
         let words = tst_utils::_words_vector_from_string("aaa bbb ccc xxx");
         let nonexisting_filter_index = log_filters.filters.len();
@@ -1693,7 +6572,7 @@ mod tests {
             (0, 0)
         );
         let expected = tst_utils::_simple_filter_from_string("mmm nnn ooo ppp");
-        assert_eq!(log_filters.filters.get(3).unwrap(), &expected);
+        assert_eq!(log_filters.filters[3].columns, expected);
 
         // One new (hence optional) word alternative added at the front of filter
         let words = tst_utils::_words_vector_from_string("foo qqq rrr sss ttt");
@@ -1702,13 +6581,10 @@ mod tests {
             (1, 1)
         );
         let mut expected = tst_utils::_simple_filter_from_string("foo qqq rrr sss ttt");
-        expected = tst_utils::_add_word_alternative(expected, 0, ".");
+        expected = tst_utils::_set_optional(expected, 0);
         expected = tst_utils::_add_word_alternative(expected, 4, "aaa");
-        assert_eq!(log_filters.filters.get(4).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"foo".to_string()).unwrap(),
-            &vec![4]
-        );
+        assert_eq!(log_filters.filters[4].columns, expected);
+        assert_eq!(log_filters.words_hash.get("foo").unwrap(), &vec![4]);
         // Two new (hence optional) word alternatives resulting from passed word vector
         let words = tst_utils::_words_vector_from_string("xyz qwe mmm nnn ooo ppp");
         assert_eq!(
@@ -1716,17 +6592,11 @@ mod tests {
             (2, 2)
         );
         let mut expected = tst_utils::_simple_filter_from_string("xyz qwe mmm nnn ooo ppp");
-        expected = tst_utils::_add_word_alternative(expected, 0, ".");
-        expected = tst_utils::_add_word_alternative(expected, 1, ".");
-        assert_eq!(log_filters.filters.get(3).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"xyz".to_string()).unwrap(),
-            &vec![3]
-        );
-        assert_eq!(
-            log_filters.words_hash.get(&"qwe".to_string()).unwrap(),
-            &vec![3]
-        );
+        expected = tst_utils::_set_optional(expected, 0);
+        expected = tst_utils::_set_optional(expected, 1);
+        assert_eq!(log_filters.filters[3].columns, expected);
+        assert_eq!(log_filters.words_hash.get("xyz").unwrap(), &vec![3]);
+        assert_eq!(log_filters.words_hash.get("qwe").unwrap(), &vec![3]);
         // One word turned to (optional) alternative as a result of words vector shorter than filter
         let words = tst_utils::_words_vector_from_string("fff ggg hhh x y z");
         assert_eq!(
@@ -1734,8 +6604,8 @@ mod tests {
             (0, 1)
         );
         let mut expected = tst_utils::_simple_filter_from_string("eee fff ggg hhh x y z");
-        expected = tst_utils::_add_word_alternative(expected, 0, ".");
-        assert_eq!(log_filters.filters.get(1).unwrap(), &expected);
+        expected = tst_utils::_set_optional(expected, 0);
+        assert_eq!(log_filters.filters[1].columns, expected);
         // Two words turned to (optional) alternatives as a resulting of words vector shorter than filter
         let words = tst_utils::_words_vector_from_string("kkk lll");
         assert_eq!(
@@ -1743,9 +6613,9 @@ mod tests {
             (0, 2)
         );
         let mut expected = tst_utils::_simple_filter_from_string("iii jjj kkk lll");
-        expected = tst_utils::_add_word_alternative(expected, 0, ".");
-        expected = tst_utils::_add_word_alternative(expected, 1, ".");
-        assert_eq!(log_filters.filters.get(2).unwrap(), &expected);
+        expected = tst_utils::_set_optional(expected, 0);
+        expected = tst_utils::_set_optional(expected, 1);
+        assert_eq!(log_filters.filters[2].columns, expected);
         // One word turned to optional alternative and one new alternative added to second word
         let words = tst_utils::_words_vector_from_string("bar ccc sss");
         assert_eq!(
@@ -1753,23 +6623,23 @@ mod tests {
             (1, 2)
         );
         let mut expected = tst_utils::_simple_filter_from_string("aaa qqq ccc sss");
-        expected = tst_utils::_add_word_alternative(expected, 0, ".");
+        expected = tst_utils::_set_optional(expected, 0);
         expected = tst_utils::_add_word_alternative(expected, 1, "bbb");
         expected = tst_utils::_add_word_alternative(expected, 1, "bar");
         expected = tst_utils::_add_word_alternative(expected, 2, "rrr");
         expected = tst_utils::_add_word_alternative(expected, 3, "ddd");
-        assert_eq!(log_filters.filters.get(0).unwrap(), &expected);
+        assert_eq!(log_filters.filters[0].columns, expected);
 
         // Tests covering when both filter and words vector do not start from column 0 and both are different indexes
         let mut log_filters = tst_utils::_init_test_data();
         log_filters.max_allowed_new_alternatives = 1;
         // Test empty words vector on valid filters
         assert_eq!(
-            log_filters.normalise_lengths_before_first_match(&vec![], 5, 3, 2),
+            log_filters.normalise_lengths_before_first_match(&[], 5, 3, 2),
             (-1, -1)
         );
         let expected = tst_utils::_simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
+        assert_eq!(log_filters.filters[5].columns, expected);
 
         // both filter and words vector match first word
         let mut log_filters = tst_utils::_init_test_data();
@@ -1786,7 +6656,7 @@ mod tests {
             (3, 2)
         );
         let expected = tst_utils::_simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
+        assert_eq!(log_filters.filters[5].columns, expected);
 
         // first filter's alternative matches second word
         let mut log_filters = tst_utils::_init_test_data();
@@ -1804,12 +6674,9 @@ mod tests {
             (4, 3)
         );
         let mut expected = tst_utils::_simple_filter_from_string("ttt aaa xyz uuu bbb ccc ddd vvv");
-        expected = tst_utils::_add_word_alternative(expected, 2, ".");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"xyz".to_string()).unwrap(),
-            &vec![5]
-        );
+        expected = tst_utils::_set_optional(expected, 2);
+        assert_eq!(log_filters.filters[5].columns, expected);
+        assert_eq!(log_filters.words_hash.get("xyz").unwrap(), &vec![5]);
 
         // second filter's alternative matches second word
         let mut log_filters = tst_utils::_init_test_data();
@@ -1828,11 +6695,8 @@ mod tests {
         );
         let mut expected = tst_utils::_simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv");
         expected = tst_utils::_add_word_alternative(expected, 2, "xyz");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"xyz".to_string()).unwrap(),
-            &vec![5]
-        );
+        assert_eq!(log_filters.filters[5].columns, expected);
+        assert_eq!(log_filters.words_hash.get("xyz").unwrap(), &vec![5]);
 
         // words missing first alternative and second alternative with new option
         let mut log_filters = tst_utils::_init_test_data();
@@ -1850,13 +6714,10 @@ mod tests {
             (4, 4)
         );
         let mut expected = tst_utils::_simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv");
-        expected = tst_utils::_add_word_alternative(expected, 2, ".");
+        expected = tst_utils::_set_optional(expected, 2);
         expected = tst_utils::_add_word_alternative(expected, 3, "xyz");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
-        assert_eq!(
-            log_filters.words_hash.get(&"xyz".to_string()).unwrap(),
-            &vec![5]
-        );
+        assert_eq!(log_filters.filters[5].columns, expected);
+        assert_eq!(log_filters.words_hash.get("xyz").unwrap(), &vec![5]);
 
         // no matches
         let mut log_filters = tst_utils::_init_test_data();
@@ -1868,7 +6729,7 @@ mod tests {
             (-1, -1)
         );
         let expected = tst_utils::_simple_filter_from_string("ttt aaa uuu bbb ccc ddd vvv");
-        assert_eq!(log_filters.filters.get(5).unwrap(), &expected);
+        assert_eq!(log_filters.filters[5].columns, expected);
 
         // first word matching last filter alternative with earlier match available
         let mut log_filters = tst_utils::_init_test_data();
@@ -1892,7 +6753,7 @@ mod tests {
         let mut expected = tst_utils::_simple_filter_from_string("aaa bbb ccc ddd eee fff ggg hhh");
         expected = tst_utils::_add_word_alternative(expected, 2, "lll");
         expected = tst_utils::_add_word_alternative(expected, 7, "lll");
-        assert_eq!(log_filters.filters.get(6).unwrap(), &expected);
+        assert_eq!(log_filters.filters[6].columns, expected);
     }
 
     #[test]
@@ -1900,7 +6761,7 @@ mod tests {
         let mut log_filters = LogFilters::new();
         // Test empty words vector on empty filters
         assert_eq!(
-            log_filters.get_indexes_of_earliest_matching_word(&vec![], 0, 0, 0),
+            log_filters.get_indexes_of_earliest_matching_word(&[], 0, 0, 0),
             (-1, -1)
         );
         // Test valid words vector on empty filters
@@ -1910,7 +6771,11 @@ mod tests {
             (-1, -1)
         );
         // Test valid words vector on empty filter
-        log_filters.filters.push(vec![]);
+        log_filters.push_filter(Filter {
+            id: 0,
+            columns: vec![],
+            note: None,
+        });
         assert_eq!(
             log_filters.get_indexes_of_earliest_matching_word(&words, 0, 0, 0),
             (-1, -1)
@@ -1921,7 +6786,7 @@ mod tests {
         log_filters.max_allowed_new_alternatives = 1;
         // Test empty words vector on valid filters
         assert_eq!(
-            log_filters.get_indexes_of_earliest_matching_word(&vec![], 0, 0, 0),
+            log_filters.get_indexes_of_earliest_matching_word(&[], 0, 0, 0),
             (-1, -1)
         );
         // both filter and words vector match first word
@@ -1966,7 +6831,7 @@ mod tests {
         log_filters.max_allowed_new_alternatives = 1;
         // Test empty words vector on valid filters
         assert_eq!(
-            log_filters.get_indexes_of_earliest_matching_word(&vec![], 0, 2, 2),
+            log_filters.get_indexes_of_earliest_matching_word(&[], 0, 2, 2),
             (-1, -1)
         );
         // both filter and words vector match first word
@@ -2014,7 +6879,7 @@ mod tests {
         log_filters.max_allowed_new_alternatives = 1;
         // Test empty words vector on valid filters
         assert_eq!(
-            log_filters.get_indexes_of_earliest_matching_word(&vec![], 5, 3, 2),
+            log_filters.get_indexes_of_earliest_matching_word(&[], 5, 3, 2),
             (-1, -1)
         );
         // both filter and words vector match first word
@@ -2094,39 +6959,21 @@ mod tests {
         // Test what happens if method was used on empty data structure
         let mut log_filters = LogFilters::new();
         log_filters.add_filter(tst_utils::_words_vector_from_string("aaa bbb ccc"));
+        assert_eq!(log_filters.words_hash.get("aaa").unwrap(), &vec![0]);
+        assert_eq!(log_filters.words_hash.get("bbb").unwrap(), &vec![0]);
+        assert_eq!(log_filters.words_hash.get("ccc").unwrap(), &vec![0]);
         assert_eq!(
-            log_filters.words_hash.get(&"aaa".to_string()).unwrap(),
-            &vec![0]
-        );
-        assert_eq!(
-            log_filters.words_hash.get(&"bbb".to_string()).unwrap(),
-            &vec![0]
-        );
-        assert_eq!(
-            log_filters.words_hash.get(&"ccc".to_string()).unwrap(),
-            &vec![0]
-        );
-        assert_eq!(
-            log_filters.filters.get(0).unwrap(),
-            &tst_utils::_simple_filter_from_string("aaa bbb ccc")
+            log_filters.filters[0].columns,
+            tst_utils::_simple_filter_from_string("aaa bbb ccc")
         );
         // add_filter does not check if filter already exists
         log_filters.add_filter(tst_utils::_words_vector_from_string("aaa bbb ccc"));
+        assert_eq!(log_filters.words_hash.get("aaa").unwrap(), &vec![0, 1]);
+        assert_eq!(log_filters.words_hash.get("bbb").unwrap(), &vec![0, 1]);
+        assert_eq!(log_filters.words_hash.get("ccc").unwrap(), &vec![0, 1]);
         assert_eq!(
-            log_filters.words_hash.get(&"aaa".to_string()).unwrap(),
-            &vec![0, 1]
-        );
-        assert_eq!(
-            log_filters.words_hash.get(&"bbb".to_string()).unwrap(),
-            &vec![0, 1]
-        );
-        assert_eq!(
-            log_filters.words_hash.get(&"ccc".to_string()).unwrap(),
-            &vec![0, 1]
-        );
-        assert_eq!(
-            log_filters.filters.get(1).unwrap(),
-            &tst_utils::_simple_filter_from_string("aaa bbb ccc")
+            log_filters.filters[1].columns,
+            tst_utils::_simple_filter_from_string("aaa bbb ccc")
         );
     }
 
@@ -2136,13 +6983,13 @@ mod tests {
         let mut log_filters = LogFilters::new();
         let word = "xxx".to_string();
         log_filters.update_hash(&word, 0);
-        assert_eq!(log_filters.words_hash.get(&word).is_some(), false);
+        assert!(!log_filters.words_hash.contains_key(&word));
 
         let mut log_filters = tst_utils::_init_test_data();
         // Trying to add a word not found in any filter
         let word = "xyz".to_string();
         log_filters.update_hash(&word, 0);
-        assert_eq!(log_filters.words_hash.get(&word).is_some(), false);
+        assert!(!log_filters.words_hash.contains_key(&word));
         // Trying to add already existing word should change nothing
         let word = "aaa".to_string();
         assert_eq!(log_filters.words_hash.get(&word).unwrap(), &vec![0, 4, 5]);
@@ -2150,11 +6997,13 @@ mod tests {
         assert_eq!(log_filters.words_hash.get(&word).unwrap(), &vec![0, 4, 5]);
         // Adding new word to hash just after new filter was added
         let word = "xyz".to_string();
-        log_filters
-            .filters
-            .push(tst_utils::_simple_filter_from_string(&word));
+        log_filters.push_filter(Filter {
+            id: 6,
+            columns: tst_utils::_simple_filter_from_string(&word),
+            note: None,
+        });
         let last_index: usize = log_filters.filters.len() - 1;
-        assert_eq!(log_filters.words_hash.get(&word).is_some(), false);
+        assert!(!log_filters.words_hash.contains_key(&word));
         log_filters.update_hash(&word, last_index);
         assert_eq!(
             log_filters.words_hash.get(&word).unwrap(),
@@ -2162,7 +7011,7 @@ mod tests {
         );
         // Adding new word to hash when extending existing filter
         let word = "iii".to_string();
-        log_filters.filters[0].push(vec![word.clone()]);
+        log_filters.filters[0].columns.push(Column::new(&word));
         assert_eq!(log_filters.words_hash.get(&word).unwrap(), &vec![2]);
         log_filters.update_hash(&word, 0);
         assert_eq!(log_filters.words_hash.get(&word).unwrap(), &vec![0, 2]);
@@ -2171,15 +7020,12 @@ mod tests {
     #[test]
     fn is_word_in_filter() {
         let log_filters = tst_utils::_init_test_data();
-        assert_eq!(log_filters.is_word_in_filter(&"aaa".to_string(), 0), true);
-        assert_eq!(log_filters.is_word_in_filter(&"aaa".to_string(), 4), true);
-        assert_eq!(log_filters.is_word_in_filter(&"hhh".to_string(), 1), true);
-        assert_eq!(log_filters.is_word_in_filter(&"aaa".to_string(), 1), false);
-        assert_eq!(log_filters.is_word_in_filter(&"xxx".to_string(), 2), false);
-        assert_eq!(
-            log_filters.is_word_in_filter(&"xxx".to_string(), log_filters.filters.len()),
-            false
-        );
-        assert_eq!(log_filters.is_word_in_filter(&"".to_string(), 0), false);
+        assert!(log_filters.is_word_in_filter("aaa", 0));
+        assert!(log_filters.is_word_in_filter("aaa", 4));
+        assert!(log_filters.is_word_in_filter("hhh", 1));
+        assert!(!log_filters.is_word_in_filter("aaa", 1));
+        assert!(!log_filters.is_word_in_filter("xxx", 2));
+        assert!(!log_filters.is_word_in_filter("xxx", log_filters.filters.len()));
+        assert!(!log_filters.is_word_in_filter("", 0));
     }
 }
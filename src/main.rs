@@ -1,14 +1,120 @@
 extern crate getopts;
+extern crate logmap;
 
 use std::env;
 use std::io::{self, BufRead};
 use std::path::Path;
 use std::process::exit;
 
-mod logmap;
+use logmap::error::LogmapError;
+use logmap::logmap::{
+    FileFormat, FilterChange, FilterId, LearnOutcome, LogFilters, LogFiltersConfig,
+};
+use logmap::mask::{Mask, Masker};
+
+/// Exit code used when command line arguments are invalid
+const EXIT_USAGE_ERROR: i32 = 1;
+/// Exit code used when filters could not be read or written
+const EXIT_IO_ERROR: i32 = 2;
+/// Exit code used when filter file content is invalid
+const EXIT_CORRUPTED_FILE: i32 = 3;
+
+fn exit_with_error(err: &LogmapError) -> ! {
+    eprintln!("logmap: {}", err);
+    match *err {
+        LogmapError::Io { .. } => exit(EXIT_IO_ERROR),
+        LogmapError::InvalidConfig { .. }
+        | LogmapError::UnknownFilter { .. }
+        | LogmapError::UnknownColumn { .. }
+        | LogmapError::InvalidWord { .. }
+        | LogmapError::InvalidRule { .. } => exit(EXIT_USAGE_ERROR),
+        _ => exit(EXIT_CORRUPTED_FILE),
+    }
+}
+
+/// Load filters from `path` in whichever format they were saved or exit
+fn load_or_exit(path: &str) -> LogFilters {
+    match LogFilters::load(Path::new(path)) {
+        Ok(log_filters) => log_filters,
+        Err(err) => exit_with_error(&err),
+    }
+}
+
+/// Load filters from every path in `paths` and merge them into the first ones
+fn merge_or_exit(paths: &[String]) -> LogFilters {
+    let mut log_filters = load_or_exit(&paths[0]);
+    for path in &paths[1..] {
+        for conflict in log_filters.merge(&load_or_exit(path)) {
+            eprintln!("logmap: warning: {}: {}", path, conflict);
+        }
+    }
+    log_filters
+}
+
+/// Print changes between filters loaded from `old_path` and `new_path`
+fn print_diff_or_exit(old_path: &str, new_path: &str) {
+    let old = load_or_exit(old_path);
+    let new = load_or_exit(new_path);
+    for change in old.diff(&new) {
+        println!("{}", change);
+        match change {
+            FilterChange::Added(ref filter) => println!("  + {}", new.filter_to_string(filter)),
+            FilterChange::Removed(ref filter) => println!("  - {}", old.filter_to_string(filter)),
+            FilterChange::Changed {
+                old: ref old_filter,
+                new: ref new_filter,
+                ..
+            } => {
+                println!("  - {}", old.filter_to_string(old_filter));
+                println!("  + {}", new.filter_to_string(new_filter));
+            }
+        }
+    }
+}
+
+/// Print every filter loaded from `path` as a regular expression, one per line
+fn print_regexes_or_exit(path: &str) {
+    let log_filters = load_or_exit(path);
+    for filter in log_filters.filters() {
+        match log_filters.filter_to_regex(filter) {
+            Some(regex) => println!("{}", regex),
+            None if !log_filters.masks().is_empty() => {
+                eprintln!(
+                    "logmap: {}: filters learned with masked words can't be exported as regular expressions",
+                    path
+                );
+                exit(EXIT_USAGE_ERROR);
+            }
+            None => {
+                eprintln!(
+                    "logmap: {}: filters split by `{}` tokenizer can't be exported as regular expressions",
+                    path,
+                    log_filters.tokenizer().kind()
+                );
+                exit(EXIT_USAGE_ERROR);
+            }
+        }
+    }
+}
+
+fn parse_uint_or_exit(option: &str, value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(value) => value,
+        Err(_) => {
+            eprintln!("logmap: couldn't parse `{}` to UINT: {}", option, value);
+            exit(EXIT_USAGE_ERROR);
+        }
+    }
+}
 
 pub fn main() {
     let args: Vec<String> = env::args().collect();
+    let brief = "Usage: logmap [OPTIONS]\n       logmap merge PATH PATH... [OPTIONS]\n       \
+                 logmap diff OLD_PATH NEW_PATH\n       \
+                 logmap export --regex PATH\n\n\
+                 `merge` combines filters loaded from given paths into filters of the first one\n\
+                 `diff` lists filters added, removed or changed between two filter files\n\
+                 `export` prints filters in other forms";
     let mut opts = getopts::Options::new();
 
     opts.optopt(
@@ -23,9 +129,28 @@ pub fn main() {
         "Save filters under given path, does not work when piping",
         "PATH",
     );
-    opts.optopt("c", "columns", "Ignore first N columns of input\ncolumns are created by splitting line by .,:/[]{}() \'\"\ndefault value: 2\nnote: set this value to a number allowing to ignore time stamp)", "UINT");
-    opts.optopt("a", "allowed-alternatives", "during analysis each new line will be allowed not to match N times\ndefault value: 0\nrecommended value when analysing: 1 or 2", "UINT");
-    opts.optflag("i", "ignore-numeric", "DO NOT ignore words containing only numbers\ndefault value: true (words containing only values are removed before analysing)");
+    opts.optflag(
+        "b",
+        "backup",
+        "Works only in conjunction with `s`. Keep previous file under the same path with `.bak` appended",
+    );
+    opts.optopt(
+        "f",
+        "format",
+        "Format of files written with `s`: text, json (needs `json` feature) or binary\ndefault value: text\nnote: files are loaded in whichever format they were saved",
+        "FORMAT",
+    );
+    opts.optopt("c", "columns", "Ignore first N columns of input\ncolumns are created by splitting line by characters given with `D`\ndefault value: 2\nnote: set this value to a number allowing to ignore time stamp)\nnote: overrides value loaded with `l`", "UINT");
+    opts.optopt("D", "delimiters", "Characters separating words of input lines\ndefault value: space and /,.:\"'(){}[]\nnote: value loaded with `l` is kept, filters were learned with it", "CHARS");
+    opts.optopt("M", "mask", "Replace words of given classes with placeholders such as <IP> before splitting lines\nCLASSES: comma separated url, email, uuid, mac, ip and hex, or all\ndefault value: none\nnote: value loaded with `l` is kept, filters were learned with it", "CLASSES");
+    opts.optopt("a", "allowed-alternatives", "during analysis each new line will be allowed not to match N times\ndefault value: 0\nrecommended value when analysing: 1 or 2\nnote: overrides value loaded with `l`", "UINT");
+    opts.optflag("i", "ignore-numeric", "DO NOT ignore words containing only numbers\ndefault value: true (words containing only values are removed before analysing)\nnote: overrides value loaded with `l`");
+    opts.optmulti(
+        "r",
+        "remove-filter",
+        "Remove filter with given ID from loaded filters, can be given multiple times",
+        "ID",
+    );
     opts.optflag(
         "m",
         "map",
@@ -36,61 +161,137 @@ pub fn main() {
         "passive",
         "Works only in conjunction with `l`. Analyse logs using loaded filters.",
     );
+    opts.optmulti(
+        "",
+        "rules",
+        "Works only in conjunction with `p`. Load hand-written rules from given path, lines matching any rule are known, can be given multiple times",
+        "PATH",
+    );
+    opts.optflag(
+        "v",
+        "verbose",
+        "Works only in conjunction with `m`. Print to standard error how each line changed filters.",
+    );
+    opts.optflag(
+        "",
+        "regex",
+        "Works only with `export`. Print each filter as anchored regular expression",
+    );
     opts.optflag("d", "debug", "Print internal data structure");
     opts.optflag("h", "help", "Print this help menu");
 
     let matches = match opts.parse(&args) {
         Ok(_option) => _option,
         Err(_) => {
-            println!("{}", opts.usage(""));
-            exit(1);
+            println!("{}", opts.usage(brief));
+            exit(EXIT_USAGE_ERROR);
         }
     };
 
     if matches.opt_present("h") {
-        println!("{}", opts.usage(""));
+        println!("{}", opts.usage(brief));
         exit(0);
     }
 
-    let mut log_filters = logmap::LogFilters::new();
-    log_filters.ignore_first_columns = 2;
-    log_filters.max_allowed_new_alternatives = 0;
-    log_filters.ignore_numeric_words = true;
-
-    if matches.opt_str("c").is_some() {
-        log_filters.ignore_first_columns =
-            match matches.opt_str("c").unwrap().to_string().parse::<usize>() {
-                Err(_) => panic!(
-                    "Couldn't parse `columns` to UINT: {}",
-                    matches.opt_str("c").unwrap()
-                ),
-                Ok(value) => value,
-            };
-    }
-    if matches.opt_str("a").is_some() {
-        log_filters.max_allowed_new_alternatives =
-            match matches.opt_str("a").unwrap().to_string().parse::<usize>() {
-                Err(_) => panic!(
-                    "Couldn't parse `columns` to UINT: {}",
-                    matches.opt_str("a").unwrap()
-                ),
-                Ok(value) => value,
-            };
-    }
-    if matches.opt_str("i").is_some() {
-        log_filters.ignore_numeric_words = false;
-    }
-    if matches.opt_str("l").is_some() {
-        let file_path_str = matches.opt_str("l").unwrap();
-        let load_file_path = Path::new(&file_path_str);
-        log_filters = logmap::LogFilters::load(load_file_path);
+    let format = match matches.opt_str("f") {
+        Some(format) => match format.parse::<FileFormat>() {
+            Ok(format) => format,
+            Err(err) => exit_with_error(&err),
+        },
+        None => FileFormat::Text,
+    };
+
+    // Only values given explicitly on the command line are set here,
+    // see `LogFilters::apply_overrides` for how they combine with loaded filters
+    let mut config = LogFiltersConfig::new();
+    if let Some(columns) = matches.opt_str("c") {
+        config = config.with_ignore_first_columns(parse_uint_or_exit("columns", &columns));
+    }
+    if let Some(alternatives) = matches.opt_str("a") {
+        config = config.with_max_allowed_new_alternatives(parse_uint_or_exit(
+            "allowed-alternatives",
+            &alternatives,
+        ));
+    }
+    if matches.opt_present("i") {
+        config = config.with_ignore_numeric_words(false);
+    }
+    if let Some(delimiters) = matches.opt_str("D") {
+        config = config.with_word_delimiters(&delimiters);
+    }
+    if let Some(classes) = matches.opt_str("M") {
+        config = match classes.as_str() {
+            "all" => config.with_masks(&Mask::ALL),
+            _ => match classes.parse::<Masker>() {
+                Ok(masker) => config.with_masks(masker.masks()),
+                Err(err) => exit_with_error(&err),
+            },
+        };
+    }
+
+    // First free argument is the program itself
+    let merged = match matches.free.get(1).map(|command| command.as_str()) {
+        Some("merge") if matches.free.len() > 3 && !matches.opt_present("l") => {
+            Some(merge_or_exit(&matches.free[2..]))
+        }
+        Some("diff") if matches.free.len() == 4 => {
+            print_diff_or_exit(&matches.free[2], &matches.free[3]);
+            exit(0);
+        }
+        Some("export") if matches.free.len() == 3 && matches.opt_present("regex") => {
+            print_regexes_or_exit(&matches.free[2]);
+            exit(0);
+        }
+        None => None,
+        _ => {
+            println!("{}", opts.usage(brief));
+            exit(EXIT_USAGE_ERROR);
+        }
+    };
+
+    let loaded = merged.or_else(|| {
+        matches
+            .opt_str("l")
+            .map(|file_path_str| load_or_exit(&file_path_str))
+    });
+    let mut log_filters = match loaded {
+        Some(mut log_filters) => {
+            match log_filters.apply_overrides(&config) {
+                Ok(conflicts) => {
+                    for conflict in conflicts {
+                        eprintln!("logmap: warning: {}", conflict);
+                    }
+                }
+                Err(err) => exit_with_error(&err),
+            }
+            log_filters
+        }
+        None => match config.build() {
+            Ok(log_filters) => log_filters,
+            Err(err) => exit_with_error(&err),
+        },
+    };
+    for id in matches.opt_strs("r") {
+        let id = parse_uint_or_exit("remove-filter", &id) as FilterId;
+        if let Err(err) = log_filters.remove_filter(id) {
+            exit_with_error(&err);
+        }
     }
     if matches.opt_present("m") {
+        let audit = matches.opt_present("v");
         let std_in = io::stdin();
         let mut icnt = 0;
         for line in std_in.lock().lines() {
             let log_line = line.expect("INVALID INPUT!");
-            log_filters.learn_line(&log_line);
+            let outcome = log_filters.learn_line(&log_line);
+            if audit {
+                match outcome {
+                    LearnOutcome::NewFilter { .. } | LearnOutcome::Extended { .. } => {
+                        eprintln!("{}: {}", outcome, log_line)
+                    }
+                    LearnOutcome::Skipped | LearnOutcome::Unchanged { .. } => {}
+                }
+            }
 
             // Debug to help assessing performance
             icnt += 1;
@@ -103,6 +304,11 @@ pub fn main() {
         log_filters.print();
     }
     if matches.opt_present("p") {
+        for path in matches.opt_strs("rules") {
+            if let Err(err) = log_filters.load_rules(Path::new(&path)) {
+                exit_with_error(&err);
+            }
+        }
         let std_in = io::stdin();
         for line in std_in.lock().lines() {
             let log_line = line.expect("INVALID INPUT!");
@@ -115,7 +321,15 @@ pub fn main() {
     if matches.opt_str("s").is_some() {
         let file_path_str = matches.opt_str("s").unwrap();
         let save_file_path = Path::new(&file_path_str);
-        log_filters.save(&save_file_path);
+        let saved = if matches.opt_present("b") {
+            log_filters.save_with_backup(save_file_path, format)
+        } else {
+            log_filters.save_as(save_file_path, format)
+        };
+        match saved {
+            Ok(_) => eprintln!("Successfully wrote to {}", save_file_path.display()),
+            Err(err) => exit_with_error(&err),
+        }
     }
     exit(0);
 }
@@ -0,0 +1,294 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use error::LogmapError;
+use regex::{Captures, Regex};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Class of variable words replaced by a typed placeholder before lines are split into words
+///
+/// Classes are listed in order of precedence: where matches of two classes start at the same
+/// character, e.g. an IP address inside an URL, the earlier one is masked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Mask {
+    /// `scheme://...` up to the next whitespace or quote, as `<URL>`
+    Url,
+    /// `user@host.domain`, as `<EMAIL>`
+    Email,
+    /// `8-4-4-4-12` hex digits, as `<UUID>`
+    Uuid,
+    /// Six pairs of hex digits separated by `:` or `-`, as `<MAC>`
+    Mac,
+    /// IPv4 and IPv6 addresses, compressed IPv6 ones (with `::`) only if they hold a digit, as `<IP>`
+    Ip,
+    /// `0x` prefixed numbers and words of at least 8 hex digits mixing digits and letters, as `<HEX>`
+    Hex,
+}
+
+impl Mask {
+    /// Every class, in order of precedence
+    pub const ALL: [Mask; 6] = [
+        Mask::Url,
+        Mask::Email,
+        Mask::Uuid,
+        Mask::Mac,
+        Mask::Ip,
+        Mask::Hex,
+    ];
+
+    /// Name used in filter files and on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Mask::Url => "url",
+            Mask::Email => "email",
+            Mask::Uuid => "uuid",
+            Mask::Mac => "mac",
+            Mask::Ip => "ip",
+            Mask::Hex => "hex",
+        }
+    }
+
+    /// Word replacing masked text
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            Mask::Url => "<URL>",
+            Mask::Email => "<EMAIL>",
+            Mask::Uuid => "<UUID>",
+            Mask::Mac => "<MAC>",
+            Mask::Ip => "<IP>",
+            Mask::Hex => "<HEX>",
+        }
+    }
+
+    fn pattern(self) -> &'static str {
+        match self {
+            Mask::Url => r#"\b[A-Za-z][A-Za-z0-9+.-]*://[^\s"'<>()\[\]{}]*[^\s"'<>()\[\]{}.,;:!?]"#,
+            Mask::Email => r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b",
+            Mask::Uuid => {
+                r"\b[0-9A-Fa-f]{8}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{12}\b"
+            }
+            Mask::Mac => {
+                r"\b[0-9A-Fa-f]{2}(?::[0-9A-Fa-f]{2}){5}\b|\b[0-9A-Fa-f]{2}(?:-[0-9A-Fa-f]{2}){5}\b"
+            }
+            Mask::Ip => {
+                // IPv4, full IPv6 and IPv6 compressed in the middle, at the start or at the end
+                r"\b(?:(?:25[0-5]|2[0-4][0-9]|1[0-9]{2}|[1-9]?[0-9])\.){3}(?:25[0-5]|2[0-4][0-9]|1[0-9]{2}|[1-9]?[0-9])\b|\b(?:[0-9A-Fa-f]{1,4}:){7}[0-9A-Fa-f]{1,4}\b|\b(?:[0-9A-Fa-f]{1,4}:){1,6}(?::[0-9A-Fa-f]{1,4}){1,6}\b|\B::(?:[0-9A-Fa-f]{1,4}:){0,6}[0-9A-Fa-f]{1,4}\b|\b(?:[0-9A-Fa-f]{1,4}:){1,7}:\B"
+            }
+            Mask::Hex => r"\b0[xX][0-9A-Fa-f]+\b|\b[0-9A-Fa-f]{8,}\b",
+        }
+    }
+}
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Mask {
+    type Err = LogmapError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match Mask::ALL.iter().find(|mask| mask.name() == name) {
+            Some(mask) => Ok(*mask),
+            None => Err(LogmapError::InvalidConfig {
+                parameter: "masks",
+                value: name.to_string(),
+                reason: "expected url, email, uuid, mac, ip or hex",
+            }),
+        }
+    }
+}
+
+/// Replaces text of enabled `Mask` classes with their placeholders
+#[derive(Clone, Debug)]
+pub struct Masker {
+    masks: Vec<Mask>,
+    /// One named group per mask, `None` when no mask is enabled
+    regex: Option<Regex>,
+}
+
+impl Masker {
+    pub fn new(masks: &[Mask]) -> Self {
+        let mut masks = masks.to_vec();
+        masks.sort();
+        masks.dedup();
+        let regex = if masks.is_empty() {
+            None
+        } else {
+            let groups: Vec<String> = masks
+                .iter()
+                .map(|mask| format!("(?P<{}>{})", mask.name(), mask.pattern()))
+                .collect();
+            Some(Regex::new(&groups.join("|")).expect("Mask patterns must be valid"))
+        };
+
+        Masker { masks, regex }
+    }
+
+    /// Enabled classes, in order of precedence
+    pub fn masks(&self) -> &[Mask] {
+        &self.masks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.masks.is_empty()
+    }
+
+    /// `log_line` with text of enabled classes replaced by placeholders
+    pub fn mask<'a>(&self, log_line: &'a str) -> Cow<'a, str> {
+        let regex = match self.regex {
+            Some(ref regex) => regex,
+            None => return Cow::Borrowed(log_line),
+        };
+        regex.replace_all(log_line, |captures: &Captures| {
+            for mask in &self.masks {
+                if let Some(found) = captures.name(mask.name()) {
+                    let text = found.as_str();
+                    let has_digit = text.chars().any(|c| c.is_ascii_digit());
+                    let is_masked = match *mask {
+                        // Plain numbers are left to `ignore_numeric_words`, plain letters are words
+                        Mask::Hex => {
+                            text.starts_with("0x")
+                                || text.starts_with("0X")
+                                || (has_digit && text.chars().any(|c| c.is_ascii_alphabetic()))
+                        }
+                        // Letters joined by `::` are paths such as `dead::beef`
+                        Mask::Ip => has_digit || !text.contains("::"),
+                        _ => true,
+                    };
+                    return if is_masked {
+                        mask.placeholder().to_string()
+                    } else {
+                        text.to_string()
+                    };
+                }
+            }
+            captures[0].to_string()
+        })
+    }
+}
+
+impl Default for Masker {
+    fn default() -> Self {
+        Masker::new(&[])
+    }
+}
+
+impl PartialEq for Masker {
+    fn eq(&self, other: &Self) -> bool {
+        self.masks == other.masks
+    }
+}
+
+/// Names of enabled classes separated by `,`
+impl fmt::Display for Masker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = self.masks.iter().map(|mask| mask.name()).collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+/// Parses names separated by `,` as written by `Display`, empty string enables no class
+impl FromStr for Masker {
+    type Err = LogmapError;
+
+    fn from_str(names: &str) -> Result<Self, Self::Err> {
+        let masks = names
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(|name| name.parse::<Mask>())
+            .collect::<Result<Vec<Mask>, LogmapError>>()?;
+        Ok(Masker::new(&masks))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Masker {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Masker {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask() {
+        let masker = Masker::new(&Mask::ALL);
+        for &(line, masked) in &[
+            (
+                "GET https://10.0.0.1:8443/api/v1?id=7, took 12ms",
+                "GET <URL>, took 12ms",
+            ),
+            (
+                "mail from john.doe+x@mail.example.com.",
+                "mail from <EMAIL>.",
+            ),
+            (
+                "job 123e4567-e89b-12d3-A456-426614174000 done",
+                "job <UUID> done",
+            ),
+            (
+                "link 00:1a:2b:3c:4d:5e up, 00-1A-2B-3C-4D-5E",
+                "link <MAC> up, <MAC>",
+            ),
+            ("from 192.168.1.254 port 22", "from <IP> port 22"),
+            ("from fe80::1ff:fe23:4567:890a%eth0", "from <IP>%eth0"),
+            ("to 2001:0db8:85a3:0000:0000:8a2e:0370:7334.", "to <IP>."),
+            (
+                "bind ::1 [::1]:80 fe80:: ff02::fb",
+                "bind <IP> [<IP>]:80 <IP> <IP>",
+            ),
+            ("fault at 0x7f3a and 7f3a9c0d12", "fault at <HEX> and <HEX>"),
+            // Look-alikes stay
+            (
+                "at 12:00:01 v1.2.3 256.1.1.1",
+                "at 12:00:01 v1.2.3 256.1.1.1",
+            ),
+            (
+                "std::vec deadbeefcafe 1697040000",
+                "std::vec deadbeefcafe 1697040000",
+            ),
+            (
+                "dead::beef a::b Foo::new std::f64 ab::",
+                "dead::beef a::b Foo::new std::f64 ab::",
+            ),
+        ] {
+            assert_eq!(masker.mask(line), masked, "{:?}", line);
+        }
+
+        // Disabled classes stay, text of an earlier class wins
+        let masker = Masker::new(&[Mask::Hex, Mask::Ip]);
+        assert_eq!(
+            masker.mask("http://10.0.0.1/ 123e4567-e89b-12d3-a456-426614174000"),
+            "http://<IP>/ <HEX>-e89b-12d3-a456-426614174000"
+        );
+        assert!(Masker::default().is_empty());
+        assert_eq!(Masker::default().mask("10.0.0.1"), "10.0.0.1");
+    }
+
+    #[test]
+    fn from_str() {
+        let masker = "ip,uuid,ip".parse::<Masker>().unwrap();
+        assert_eq!(masker.masks(), [Mask::Uuid, Mask::Ip]);
+        assert_eq!(masker.to_string(), "uuid,ip");
+        assert!("".parse::<Masker>().unwrap().is_empty());
+        assert_eq!(
+            "ip,guid".parse::<Masker>().unwrap_err().to_string(),
+            "invalid `masks` value \"guid\": expected url, email, uuid, mac, ip or hex"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+
+use error::LogmapError;
+use regex::Regex;
+
+/// Default for `PunctuationTokenizer` delimiters
+pub const DEFAULT_WORD_DELIMITERS: &str = " /,.:\"'(){}[]";
+/// Characters split on by `WhitespaceTokenizer`
+const ASCII_WHITESPACE: &[char] = &[' ', '\t', '\n', '\x0c', '\r'];
+
+/// Splits log lines into words compared with filter columns
+///
+/// Filters are only valid for lines split the same way they were learned, so `kind` and
+/// `settings` are saved in filter files and loading builds the same tokenizer again.
+/// Files learned with tokenizers other than the built-in ones can't be loaded.
+pub trait Tokenizer: fmt::Debug + Send + Sync {
+    /// Non-empty words of `log_line` in order of appearance
+    fn tokens(&self, log_line: &str) -> Vec<String>;
+
+    /// Name saved in filter files, distinct for each implementation
+    fn kind(&self) -> &str;
+
+    /// Parameters saved in filter files next to `kind`, named neither `kind` nor `numeric_symbols`
+    fn settings(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// Characters words are separated by, if the tokenizer splits on single characters only
+    ///
+    /// Needed by `LogFilters::filter_to_regex`.
+    fn delimiters(&self) -> Option<Vec<char>> {
+        None
+    }
+}
+
+/// Splits on any of given characters, `DEFAULT_WORD_DELIMITERS` by default
+#[derive(Clone, Debug, PartialEq)]
+pub struct PunctuationTokenizer {
+    delimiters: String,
+}
+
+impl PunctuationTokenizer {
+    pub fn new(delimiters: &str) -> Self {
+        PunctuationTokenizer {
+            delimiters: delimiters.to_string(),
+        }
+    }
+}
+
+impl Default for PunctuationTokenizer {
+    fn default() -> Self {
+        PunctuationTokenizer::new(DEFAULT_WORD_DELIMITERS)
+    }
+}
+
+impl Tokenizer for PunctuationTokenizer {
+    fn tokens(&self, log_line: &str) -> Vec<String> {
+        log_line
+            .split(|c| self.delimiters.contains(c))
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_string())
+            .collect()
+    }
+
+    fn kind(&self) -> &str {
+        "punctuation"
+    }
+
+    fn settings(&self) -> Vec<(String, String)> {
+        vec![("word_delimiters".to_string(), self.delimiters.clone())]
+    }
+
+    fn delimiters(&self) -> Option<Vec<char>> {
+        Some(self.delimiters.chars().collect())
+    }
+}
+
+/// Splits on ASCII whitespace only, punctuation stays within words
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WhitespaceTokenizer;
+
+impl Tokenizer for WhitespaceTokenizer {
+    fn tokens(&self, log_line: &str) -> Vec<String> {
+        log_line
+            .split(ASCII_WHITESPACE)
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_string())
+            .collect()
+    }
+
+    fn kind(&self) -> &str {
+        "whitespace"
+    }
+
+    fn delimiters(&self) -> Option<Vec<char>> {
+        Some(ASCII_WHITESPACE.to_vec())
+    }
+}
+
+/// Splits on every match of a regular expression
+#[derive(Clone, Debug)]
+pub struct RegexTokenizer {
+    regex: Regex,
+}
+
+impl RegexTokenizer {
+    /// Tokenizer splitting on matches of `pattern`, e.g. `[\s=|;]+`
+    pub fn new(pattern: &str) -> Result<Self, LogmapError> {
+        match Regex::new(pattern) {
+            Ok(regex) => Ok(RegexTokenizer { regex }),
+            Err(_) => Err(LogmapError::InvalidConfig {
+                parameter: "pattern",
+                value: pattern.to_string(),
+                reason: "invalid regular expression",
+            }),
+        }
+    }
+}
+
+impl Tokenizer for RegexTokenizer {
+    fn tokens(&self, log_line: &str) -> Vec<String> {
+        self.regex
+            .split(log_line)
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_string())
+            .collect()
+    }
+
+    fn kind(&self) -> &str {
+        "regex"
+    }
+
+    fn settings(&self) -> Vec<(String, String)> {
+        vec![("pattern".to_string(), self.regex.as_str().to_string())]
+    }
+}
+
+/// Build one of the built-in tokenizers from `kind` and `settings` saved in a filter file
+pub fn from_settings(
+    kind: &str,
+    settings: &[(String, String)],
+) -> Result<Box<dyn Tokenizer>, LogmapError> {
+    let unsupported = |setting: &str, value: &str| LogmapError::UnsupportedTokenizer {
+        path: None,
+        setting: setting.to_string(),
+        value: value.to_string(),
+        expected: None,
+    };
+    let mut word_delimiters = None;
+    let mut pattern = None;
+    for (setting, value) in settings {
+        match (kind, setting.as_str()) {
+            ("punctuation", "word_delimiters") => word_delimiters = Some(value.as_str()),
+            ("regex", "pattern") => pattern = Some(value.as_str()),
+            _ => return Err(unsupported(setting, value)),
+        }
+    }
+
+    match kind {
+        "punctuation" => Ok(Box::new(PunctuationTokenizer::new(
+            word_delimiters.unwrap_or(DEFAULT_WORD_DELIMITERS),
+        ))),
+        "whitespace" => Ok(Box::new(WhitespaceTokenizer)),
+        "regex" => match pattern {
+            Some(pattern) => Ok(Box::new(RegexTokenizer::new(pattern)?)),
+            None => Err(LogmapError::InvalidConfig {
+                parameter: "tokenizer",
+                value: kind.to_string(),
+                reason: "`pattern` setting is missing",
+            }),
+        },
+        _ => Err(unsupported("kind", kind)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        let line = "host=db1.example.com|user: a b\t[up]";
+        assert_eq!(
+            PunctuationTokenizer::default().tokens(line),
+            vec!["host=db1", "example", "com|user", "a", "b\t", "up"]
+        );
+        assert_eq!(
+            PunctuationTokenizer::new(" =|\t").tokens(line),
+            vec!["host", "db1.example.com", "user:", "a", "b", "[up]"]
+        );
+        assert_eq!(
+            WhitespaceTokenizer.tokens(line),
+            vec!["host=db1.example.com|user:", "a", "b", "[up]"]
+        );
+        assert_eq!(
+            RegexTokenizer::new(r"[\s=|:]+").unwrap().tokens(line),
+            vec!["host", "db1.example.com", "user", "a", "b", "[up]"]
+        );
+        assert!(WhitespaceTokenizer.tokens(" \t ").is_empty());
+        assert!(RegexTokenizer::new("(").is_err());
+    }
+
+    #[test]
+    fn from_settings() {
+        for tokenizer in &[
+            Box::new(PunctuationTokenizer::new(" |")) as Box<dyn Tokenizer>,
+            Box::new(WhitespaceTokenizer),
+            Box::new(RegexTokenizer::new(r"\s+").unwrap()),
+        ] {
+            let rebuilt = super::from_settings(tokenizer.kind(), &tokenizer.settings()).unwrap();
+            assert_eq!(rebuilt.kind(), tokenizer.kind());
+            assert_eq!(rebuilt.settings(), tokenizer.settings());
+        }
+        assert_eq!(
+            super::from_settings("punctuation", &[]).unwrap().settings(),
+            PunctuationTokenizer::default().settings()
+        );
+
+        let setting = |setting: &str, value: &str| vec![(setting.to_string(), value.to_string())];
+        for &(kind, ref settings) in &[
+            ("custom", vec![]),
+            ("whitespace", setting("word_delimiters", " ")),
+            ("punctuation", setting("pattern", " ")),
+            ("regex", vec![]),
+            ("regex", setting("pattern", "(")),
+        ] {
+            assert!(
+                super::from_settings(kind, settings).is_err(),
+                "{} {:?}",
+                kind,
+                settings
+            );
+        }
+    }
+}
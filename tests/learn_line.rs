@@ -1,11 +1,15 @@
 extern crate logmap;
 
+use logmap::logmap::LogFiltersConfig;
+
 #[test]
 fn no_alts_include_num_no_cols_skipped() {
-    let mut log_filters = logmap::logmap::LogFilters::new();
-    log_filters.max_allowed_new_alternatives = 0;
-    log_filters.ignore_numeric_words = false;
-    log_filters.ignore_first_columns = 0;
+    let mut log_filters = LogFiltersConfig::new()
+        .with_max_allowed_new_alternatives(0)
+        .with_ignore_numeric_words(false)
+        .with_ignore_first_columns(0)
+        .build()
+        .unwrap();
 
     log_filters.learn_line("Sep 26 09:13:15 anonymous_hostname systemd-logind[572]: Removed session c524.");
     log_filters.learn_line("Sep 27 19:27:53 anonymous_hostname systemd-logind[572]: Removed session c525.");
@@ -20,10 +24,12 @@ fn no_alts_include_num_no_cols_skipped() {
 
 #[test]
 fn no_alts_no_nums_no_cols_skipped() {
-    let mut log_filters = logmap::logmap::LogFilters::new();
-    log_filters.max_allowed_new_alternatives = 0;
-    log_filters.ignore_numeric_words = true;
-    log_filters.ignore_first_columns = 0;
+    let mut log_filters = LogFiltersConfig::new()
+        .with_max_allowed_new_alternatives(0)
+        .with_ignore_numeric_words(true)
+        .with_ignore_first_columns(0)
+        .build()
+        .unwrap();
 
     log_filters.learn_line("Sep 26 09:13:15 anonymous_hostname systemd-logind[572]: Removed session c524.");
     log_filters.learn_line("Sep 27 19:27:53 anonymous_hostname systemd-logind[572]: Removed session c525.");
@@ -38,10 +44,12 @@ fn no_alts_no_nums_no_cols_skipped() {
 
 #[test]
 fn no_alts_no_nums_extended_no_cols_skipped() {
-    let mut log_filters = logmap::logmap::LogFilters::new();
-    log_filters.max_allowed_new_alternatives = 0;
-    log_filters.ignore_numeric_words = true;
-    log_filters.ignore_first_columns = 0;
+    let mut log_filters = LogFiltersConfig::new()
+        .with_max_allowed_new_alternatives(0)
+        .with_ignore_numeric_words(true)
+        .with_ignore_first_columns(0)
+        .build()
+        .unwrap();
 
     log_filters.learn_line(&("Dec 18 09:59:36 host_name [error] 19901#19901: *180073 open() \"/path/to/file\"".to_string() +
         "failed (2: No such file or directory), client: 127.0.0.1, server: some.example.com, request:" +
@@ -56,10 +64,12 @@ fn no_alts_no_nums_extended_no_cols_skipped() {
 
 #[test]
 fn no_alts_no_nums_one_col_skipped() {
-    let mut log_filters = logmap::logmap::LogFilters::new();
-    log_filters.max_allowed_new_alternatives = 0;
-    log_filters.ignore_numeric_words = true;
-    log_filters.ignore_first_columns = 1;
+    let mut log_filters = LogFiltersConfig::new()
+        .with_max_allowed_new_alternatives(0)
+        .with_ignore_numeric_words(true)
+        .with_ignore_first_columns(1)
+        .build()
+        .unwrap();
 
     log_filters.learn_line("Sep 26 09:13:15 anonymous_hostname systemd-logind[572]: Removed session c524.");
     log_filters.learn_line("Sep 27 19:27:53 anonymous_hostname systemd-logind[572]: Removed session c525.");
@@ -74,10 +84,12 @@ fn no_alts_no_nums_one_col_skipped() {
 
 #[test]
 fn one_alt_no_nums_one_col_skipped() {
-    let mut log_filters = logmap::logmap::LogFilters::new();
-    log_filters.max_allowed_new_alternatives = 1;
-    log_filters.ignore_numeric_words = true;
-    log_filters.ignore_first_columns = 1;
+    let mut log_filters = LogFiltersConfig::new()
+        .with_max_allowed_new_alternatives(1)
+        .with_ignore_numeric_words(true)
+        .with_ignore_first_columns(1)
+        .build()
+        .unwrap();
 
     log_filters.learn_line("Sep 26 09:13:15 anonymous_hostname systemd-logind[572]: Removed session c524.");
     log_filters.learn_line("Sep 27 19:27:53 anonymous_hostname systemd-logind[572]: Removed session c525.");
@@ -90,10 +102,12 @@ fn one_alt_no_nums_one_col_skipped() {
 
 #[test]
 fn one_alt_no_nums_one_col_skipped_followed_by_short_line() {
-    let mut log_filters = logmap::logmap::LogFilters::new();
-    log_filters.max_allowed_new_alternatives = 1;
-    log_filters.ignore_numeric_words = true;
-    log_filters.ignore_first_columns = 1;
+    let mut log_filters = LogFiltersConfig::new()
+        .with_max_allowed_new_alternatives(1)
+        .with_ignore_numeric_words(true)
+        .with_ignore_first_columns(1)
+        .build()
+        .unwrap();
 
     log_filters.learn_line("Sep 26 09:13:15 anonymous_hostname systemd-logind[572]: Removed session c524.");
     log_filters.learn_line("Sep 27 19:27:53 anonymous_hostname systemd-logind[572]: Removed session c525.");
@@ -108,10 +122,12 @@ fn one_alt_no_nums_one_col_skipped_followed_by_short_line() {
 
 #[test]
 fn one_alt_no_nums_one_col_skipped_followed_by_long_line() {
-    let mut log_filters = logmap::logmap::LogFilters::new();
-    log_filters.max_allowed_new_alternatives = 1;
-    log_filters.ignore_numeric_words = true;
-    log_filters.ignore_first_columns = 1;
+    let mut log_filters = LogFiltersConfig::new()
+        .with_max_allowed_new_alternatives(1)
+        .with_ignore_numeric_words(true)
+        .with_ignore_first_columns(1)
+        .build()
+        .unwrap();
 
     log_filters.learn_line("Sep 28 13:41:26 anonymous_hostname");
     log_filters.learn_line("Sep 26 09:13:15 anonymous_hostname systemd-logind[572]: Removed session c524.");
@@ -127,10 +143,12 @@ fn one_alt_no_nums_one_col_skipped_followed_by_long_line() {
 #[test]
 fn one_alt_no_nums_one_col_skipped_repeated_filter_word_at_the_log_end() {
     // Test without duplicate at the end
-    let mut log_filters = logmap::logmap::LogFilters::new();
-    log_filters.max_allowed_new_alternatives = 1;
-    log_filters.ignore_numeric_words = true;
-    log_filters.ignore_first_columns = 1;
+    let mut log_filters = LogFiltersConfig::new()
+        .with_max_allowed_new_alternatives(1)
+        .with_ignore_numeric_words(true)
+        .with_ignore_first_columns(1)
+        .build()
+        .unwrap();
 
     log_filters.learn_line("Sep 22 22:27:52 some_hostname dolphin[7229]: org.kde.dolphin: slotUrlSelectionRequested:  QUrl(\"file:///some/path/dir1\")");
     log_filters.learn_line("Sep 22 22:28:40 some_hostname dolphin[7229]: org.kde.dolphin: slotUrlSelectionRequested:  QUrl(\"file:///some/path/dir2\")");
@@ -141,10 +159,12 @@ fn one_alt_no_nums_one_col_skipped_repeated_filter_word_at_the_log_end() {
     assert_eq!(log_filters.to_string(), expected);
 
     // Test with duplicate at the end
-    let mut log_filters = logmap::logmap::LogFilters::new();
-    log_filters.max_allowed_new_alternatives = 1;
-    log_filters.ignore_numeric_words = true;
-    log_filters.ignore_first_columns = 1;
+    let mut log_filters = LogFiltersConfig::new()
+        .with_max_allowed_new_alternatives(1)
+        .with_ignore_numeric_words(true)
+        .with_ignore_first_columns(1)
+        .build()
+        .unwrap();
 
     log_filters.learn_line("Sep 22 22:27:52 some_hostname dolphin[7229]: org.kde.dolphin: slotUrlSelectionRequested:  QUrl(\"file:///some/path/dir1\")");
     log_filters.learn_line("Sep 22 22:28:40 some_hostname dolphin[7229]: org.kde.dolphin: slotUrlSelectionRequested:  QUrl(\"file:///some/path/dir2\")");
@@ -155,10 +175,12 @@ fn one_alt_no_nums_one_col_skipped_repeated_filter_word_at_the_log_end() {
     assert_eq!(log_filters.to_string(), expected);
 
     // Test with two duplicates at the end
-    let mut log_filters = logmap::logmap::LogFilters::new();
-    log_filters.max_allowed_new_alternatives = 2;
-    log_filters.ignore_numeric_words = true;
-    log_filters.ignore_first_columns = 1;
+    let mut log_filters = LogFiltersConfig::new()
+        .with_max_allowed_new_alternatives(2)
+        .with_ignore_numeric_words(true)
+        .with_ignore_first_columns(1)
+        .build()
+        .unwrap();
 
     log_filters.learn_line("Sep 22 22:27:52 some_hostname dolphin[7229]: org.kde.dolphin: slotUrlSelectionRequested:  QUrl(\"file:///some/path/dir1\")");
     log_filters.learn_line("Sep 22 22:28:40 some_hostname dolphin[7229]: org.kde.dolphin: slotUrlSelectionRequested:  QUrl(\"file:///some/path/dir2\")");